@@ -0,0 +1,45 @@
+// Each list has an archive that finished items are moved into, out of the way of the list itself.
+// Only subtrees that are done all the way down are archived, and they keep their subtasks.
+
+use crate::{TodoItem, TodoList};
+use chrono::NaiveDateTime;
+
+// archives every finished subtree whose root passes the filter, returning how many items moved
+pub fn archive_done(
+    items: &mut Vec<TodoItem>,
+    archive: &mut Vec<TodoItem>,
+    filter: impl Fn(&TodoItem) -> bool,
+) -> usize {
+    let mut moved = 0;
+    let mut idx = 0;
+
+    while idx < items.len() {
+        let end = crate::tree::subtree_end(items, idx);
+        if items[idx..end].iter().all(|item| item.is_done) && filter(&items[idx]) {
+            moved += end - idx;
+            archive.extend(crate::tree::take_subtree(items, idx));
+        } else {
+            idx += 1;
+        }
+    }
+
+    moved
+}
+
+pub fn archive_older_than(lists: &mut [TodoList], cutoff: NaiveDateTime) -> usize {
+    lists
+        .iter_mut()
+        .map(|list| {
+            archive_done(&mut list.items, &mut list.archive, |item| {
+                item.completed_at.is_some_and(|at| at < cutoff)
+            })
+        })
+        .sum()
+}
+
+// moves the archived subtree at idx back to the end of the list, returning its new index
+pub fn restore(items: &mut Vec<TodoItem>, archive: &mut Vec<TodoItem>, idx: usize) -> usize {
+    let new_idx = items.len();
+    items.extend(crate::tree::take_subtree(archive, idx));
+    new_idx
+}
@@ -0,0 +1,296 @@
+// Subcommands that work on the list file directly, for use from scripts, without starting the UI.
+// Items are addressed by their stable id, which `list` prints.
+
+use crate::fs::{FileMeta, StorageError};
+use crate::input::TextInput;
+use crate::priority::Priority;
+use crate::{AppState, InputState, TodoItem};
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{Result, bail, eyre};
+use std::time::Duration;
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Add an item
+    Add {
+        name: String,
+        #[command(flatten)]
+        fields: ItemFields,
+        /// Add the item as a subtask of this item
+        #[arg(long, value_name = "ID")]
+        parent: Option<u64>,
+    },
+    /// Print the items of a list
+    List {
+        /// Only completed items
+        #[arg(long, conflicts_with = "pending")]
+        done: bool,
+        /// Only items that are not completed
+        #[arg(long)]
+        pending: bool,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Complete an item
+    Done { id: u64 },
+    /// Move an item and its subtasks to the trash
+    Rm { id: u64 },
+    /// Change the given fields of an item
+    Edit {
+        id: u64,
+        #[arg(long)]
+        name: Option<String>,
+        #[command(flatten)]
+        fields: ItemFields,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ItemFields {
+    /// Description
+    #[arg(long)]
+    desc: Option<String>,
+    /// Due date, e.g. 2025-03-14, tomorrow 09:00, fri or +3d
+    #[arg(long)]
+    due: Option<String>,
+    /// none, low, medium, high or urgent, or 0 to 4
+    #[arg(long)]
+    priority: Option<String>,
+    /// Tags separated by spaces or commas
+    #[arg(long)]
+    tags: Option<String>,
+    /// Repeat rule, e.g. daily, weekly mon,thu, monthly 15 or after 3d
+    #[arg(long)]
+    repeat: Option<String>,
+}
+
+impl ItemFields {
+    // fields that were not passed are left as they are
+    fn apply(self, input: &mut InputState) -> Result<()> {
+        if let Some(desc) = self.desc {
+            input.description_input = TextInput::new(desc);
+        }
+        if let Some(due) = self.due {
+            input.due_input = TextInput::new(due);
+        }
+        if let Some(priority) = self.priority {
+            input.priority =
+                Priority::parse(&priority).ok_or(eyre!("Invalid priority \"{priority}\""))?;
+        }
+        if let Some(tags) = self.tags {
+            input.tags_input = TextInput::new(tags);
+        }
+        if let Some(repeat) = self.repeat {
+            input.recurrence_input = TextInput::new(repeat);
+        }
+
+        input.validate().map_err(|e| eyre!(e))
+    }
+}
+
+// how long to wait for another program to finish writing the list
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn run(command: Command, list: Option<String>, app_state: &mut AppState) -> Result<()> {
+    // list only reads, so it neither waits for the lock nor touches the file or the journal
+    let read_only = matches!(command, Command::List { .. });
+    // held from reading to writing, so a save from the TUI cannot land in between
+    let _lock = if read_only {
+        None
+    } else {
+        Some(crate::fs::lock_for_write(
+            &app_state.file_path,
+            LOCK_TIMEOUT,
+        )?)
+    };
+    load(app_state, list, read_only)?;
+
+    match command {
+        Command::Add {
+            name,
+            fields,
+            parent,
+        } => {
+            let parent = parent.map(|id| locate(app_state, id)).transpose()?;
+
+            app_state.input_state.name_input = TextInput::new(name);
+            fields.apply(&mut app_state.input_state)?;
+            let idx = crate::add_item(app_state, parent);
+            save(app_state)?;
+
+            let item = &app_state.items[idx];
+            println!("Added {}: {}", item.id, item.name);
+        }
+        Command::List {
+            done,
+            pending,
+            json,
+        } => {
+            let items = app_state
+                .items
+                .iter()
+                .filter(|item| (!done || item.is_done) && (!pending || !item.is_done));
+
+            if json {
+                let items: Vec<_> = items.map(to_json).collect();
+                println!("{}", serde_json::to_string_pretty(&items)?);
+            } else {
+                let width = app_state
+                    .items
+                    .iter()
+                    .map(|item| item.id)
+                    .max()
+                    .unwrap_or(0);
+                let width = width.to_string().len();
+                for item in items {
+                    println!("{}", format_item(item, width));
+                }
+            }
+        }
+        Command::Done { id } => {
+            let idx = locate(app_state, id)?;
+            let item = &mut app_state.items[idx];
+
+            if let Some(recurrence) = item.recurrence.clone() {
+                crate::complete_recurring(item, &recurrence);
+            } else if item.is_done {
+                bail!("Item {id} is already done");
+            } else {
+                item.set_done(true);
+            }
+            save(app_state)?;
+
+            println!("Completed {id}: {}", app_state.items[idx].name);
+        }
+        Command::Rm { id } => {
+            let idx = locate(app_state, id)?;
+            let name = app_state.items[idx].name.clone();
+
+            crate::trash::delete(
+                &mut app_state.items,
+                &mut app_state.lists[app_state.current_list].trash,
+                idx,
+            );
+            save(app_state)?;
+
+            println!("Deleted {id}: {name}");
+        }
+        Command::Edit { id, name, fields } => {
+            let idx = locate(app_state, id)?;
+
+            app_state.input_state = InputState::from_item(&app_state.items[idx]);
+            if let Some(name) = name {
+                app_state.input_state.name_input = TextInput::new(name);
+            }
+            fields.apply(&mut app_state.input_state)?;
+            app_state.input_state.apply(&mut app_state.items[idx]);
+            save(app_state)?;
+
+            println!("Edited {id}: {}", app_state.items[idx].name);
+        }
+    }
+
+    Ok(())
+}
+
+fn load(app_state: &mut AppState, list: Option<String>, read_only: bool) -> Result<()> {
+    let (lists, file_meta) = if read_only {
+        match crate::fs::read_existing(&app_state.file_path) {
+            // nothing has been saved yet, which is an empty list
+            Err(StorageError::Missing(_)) => (Vec::new(), FileMeta::default()),
+            result => result?,
+        }
+    } else {
+        crate::fs::read(&app_state.file_path)?
+    };
+    crate::load_lists(app_state, lists);
+    app_state.file_meta = file_meta;
+
+    if let Some(name) = list {
+        let idx = app_state
+            .lists
+            .iter()
+            .position(|list| list.name.eq_ignore_ascii_case(&name))
+            .ok_or(eyre!("No list named \"{name}\""))?;
+        crate::switch_list(app_state, idx);
+    }
+
+    // so changes made here can be undone from the interface
+    if !read_only {
+        app_state.history = crate::fs::read_journal(&app_state.file_path);
+        crate::record_history(app_state);
+    }
+
+    Ok(())
+}
+
+fn save(app_state: &mut AppState) -> Result<()> {
+    crate::record_history(app_state);
+
+    crate::fs::write(&app_state.file_path, app_state, true)?;
+    Ok(crate::fs::write_journal(
+        &app_state.file_path,
+        &app_state.history,
+    )?)
+}
+
+// ids are unique across lists, so this opens whichever list holds the item
+fn locate(app_state: &mut AppState, id: u64) -> Result<usize> {
+    for list in 0..app_state.lists.len() {
+        if let Some(idx) = app_state
+            .list_items(list)
+            .iter()
+            .position(|item| item.id == id)
+        {
+            if list != app_state.current_list {
+                crate::switch_list(app_state, list);
+            }
+            return Ok(idx);
+        }
+    }
+
+    bail!("No item with id {id}")
+}
+
+fn format_item(item: &TodoItem, id_width: usize) -> String {
+    let mut line = format!(
+        "{:>id_width$} {}{} {}",
+        item.id,
+        "  ".repeat(item.depth),
+        if item.is_done { "[x]" } else { "[ ]" },
+        item.name
+    );
+
+    if let Some(date) = item.due_date {
+        line.push_str(&format!(
+            "  due {}",
+            crate::due::format(date, item.due_time)
+        ));
+    }
+    if !item.priority.is_none() {
+        line.push_str(&format!("  !{}", item.priority.label()));
+    }
+    if let Some(recurrence) = &item.recurrence {
+        line.push_str(&format!("  ({recurrence})"));
+    }
+    if !item.tags.is_empty() {
+        line.push_str(&format!("  {}", crate::tags::format(&item.tags)));
+    }
+
+    line
+}
+
+fn to_json(item: &TodoItem) -> serde_json::Value {
+    serde_json::json!({
+        "id": item.id,
+        "name": item.name,
+        "description": item.description,
+        "is_done": item.is_done,
+        "due": item.due_date.map(|date| crate::due::format(date, item.due_time)),
+        "priority": item.priority,
+        "tags": item.tags,
+        "recurrence": item.recurrence,
+        "depth": item.depth,
+    })
+}
@@ -0,0 +1,78 @@
+use crate::fs::StorageError;
+use serde::Deserialize;
+use std::env::home_dir;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FILE_ENV_VAR: &str = "TUIDOLIST_FILE";
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub path: Option<PathBuf>,
+    // number of rotating backups kept next to the list file
+    pub backups: usize,
+    // completed items older than this are archived on startup
+    pub auto_archive_days: Option<u64>,
+    // deleted items older than this are purged from the trash on startup, None keeps them forever
+    pub trash_retention_days: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            path: None,
+            backups: 3,
+            auto_archive_days: None,
+            trash_retention_days: Some(30),
+        }
+    }
+}
+
+pub fn data_dir() -> PathBuf {
+    home_dir().unwrap().join(".tuidolist")
+}
+
+// a missing config file means the defaults, a broken one is an error the caller reports
+pub fn load() -> Result<Config, StorageError> {
+    let path = data_dir().join("config.json");
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(StorageError::Read(path, e)),
+    };
+
+    serde_json::from_str(&content).map_err(|e| StorageError::Parse(path, e))
+}
+
+// order of precedence: --file, then $TUIDOLIST_FILE, then the config file, then the default
+pub fn resolve_list_path(cli_path: Option<PathBuf>, config: &Config) -> PathBuf {
+    if let Some(path) = cli_path {
+        return path;
+    }
+
+    if let Some(path) = std::env::var_os(FILE_ENV_VAR).filter(|p| !p.is_empty()) {
+        return expand_home(Path::new(&path));
+    }
+
+    if let Some(path) = &config.path {
+        let path = expand_home(path);
+        // relative paths in the config file are relative to the config directory
+        return if path.is_relative() {
+            data_dir().join(path)
+        } else {
+            path
+        };
+    }
+
+    data_dir().join("items.json")
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir().unwrap().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DueState {
+    Overdue,
+    Today,
+    Upcoming,
+}
+
+pub fn format(date: NaiveDate, time: Option<NaiveTime>) -> String {
+    match time {
+        Some(time) => format!("{} {}", date.format("%Y-%m-%d"), time.format("%H:%M")),
+        None => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+pub fn state(date: NaiveDate, time: Option<NaiveTime>) -> DueState {
+    let now = Local::now().naive_local();
+    let due = NaiveDateTime::new(date, time.unwrap_or(NaiveTime::MIN));
+
+    if date < now.date() || (time.is_some() && due < now) {
+        DueState::Overdue
+    } else if date == now.date() {
+        DueState::Today
+    } else {
+        DueState::Upcoming
+    }
+}
+
+// accepts an ISO date or a relative form (today, tomorrow, a weekday, +3d, +2w, +1m),
+// optionally followed by a time like 14:30
+pub fn parse(input: &str) -> Result<Option<(NaiveDate, Option<NaiveTime>)>, String> {
+    let mut input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    // allow both "2026-01-31T09:00" and "2026-01-31 09:00"
+    if is_iso_date_time(&input) {
+        input.replace_range(10..11, " ");
+    }
+
+    let today = Local::now().date_naive();
+    let mut parts = input.split_whitespace();
+    let date_part = parts.next().unwrap_or_default();
+    let time_part = parts.next();
+
+    if parts.next().is_some() {
+        return Err(format!("Unexpected input after time in \"{input}\""));
+    }
+
+    let date = parse_date(date_part, today)?;
+    let time = time_part.map(parse_time).transpose()?;
+
+    Ok(Some((date, time)))
+}
+
+fn is_iso_date_time(input: &str) -> bool {
+    input.len() > 10 && input.as_bytes()[10] == b't' && input[..10].parse::<NaiveDate>().is_ok()
+}
+
+fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    match input {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Days::new(1)),
+        "yesterday" => return Ok(today - Days::new(1)),
+        _ => {}
+    }
+
+    if let Ok(weekday) = input.parse::<Weekday>() {
+        // the next occurrence, never today
+        let ahead = (weekday.days_since(today.weekday()) + 6) % 7 + 1;
+        return Ok(today + Days::new(ahead.into()));
+    }
+
+    if let Some(offset) = input.strip_prefix('+') {
+        let split = offset
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(offset.len());
+        let (amount, unit) = offset.split_at(split);
+        let amount: u32 = amount
+            .parse()
+            .map_err(|_| format!("Invalid offset \"{input}\""))?;
+
+        let date = match unit {
+            "" | "d" => today.checked_add_days(Days::new(amount.into())),
+            "w" => today.checked_add_days(Days::new(u64::from(amount) * 7)),
+            "m" => today.checked_add_months(Months::new(amount)),
+            "y" => today.checked_add_months(Months::new(amount.saturating_mul(12))),
+            _ => return Err(format!("Unknown unit \"{unit}\", expected d, w, m or y")),
+        };
+
+        return date.ok_or(format!("Offset \"{input}\" is out of range"));
+    }
+
+    Err(format!("Invalid date \"{input}\""))
+}
+
+fn parse_time(input: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .map_err(|_| format!("Invalid time \"{input}\", expected HH:MM"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_iso_dates_and_times() {
+        assert_eq!(parse("2026-01-31"), Ok(Some((date(2026, 1, 31), None))));
+        assert_eq!(
+            parse("2026-01-31 09:00"),
+            Ok(Some((date(2026, 1, 31), Some(time(9, 0)))))
+        );
+        assert_eq!(
+            parse("2026-01-31T14:30"),
+            Ok(Some((date(2026, 1, 31), Some(time(14, 30)))))
+        );
+        assert_eq!(parse("  "), Ok(None));
+    }
+
+    #[test]
+    fn parses_dates_relative_to_today() {
+        let today = date(2026, 1, 28);
+
+        assert_eq!(parse_date("today", today), Ok(today));
+        assert_eq!(parse_date("tomorrow", today), Ok(date(2026, 1, 29)));
+        assert_eq!(parse_date("+3d", today), Ok(date(2026, 1, 31)));
+        assert_eq!(parse_date("+2w", today), Ok(date(2026, 2, 11)));
+        assert_eq!(parse_date("+1m", today), Ok(date(2026, 2, 28)));
+        // 2026-01-28 is a Wednesday, and the same weekday means next week
+        assert_eq!(parse_date("fri", today), Ok(date(2026, 1, 30)));
+        assert_eq!(parse_date("wed", today), Ok(date(2026, 2, 4)));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(parse("someday").is_err());
+        assert!(parse("2026-02-30").is_err());
+        assert!(parse("today 25:00").is_err());
+        assert!(parse("today 09:00 extra").is_err());
+        assert!(parse("+3x").is_err());
+    }
+}
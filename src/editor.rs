@@ -0,0 +1,87 @@
+// Hands an item's title and description to the user's editor as a plain text file:
+// the first line is the title and everything after the blank line below it is the description.
+
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+// None when the title was left empty, which cancels the edit
+pub fn edit(name: &str, description: &str) -> Result<Option<(String, String)>, String> {
+    let path = std::env::temp_dir().join(format!("tuidolist-{}.md", std::process::id()));
+    let mut file =
+        create_private(&path).map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    if let Err(e) = file.write_all(format!("{name}\n\n{description}\n").as_bytes()) {
+        let _ = fs::remove_file(&path);
+        return Err(format!("Failed to write {}: {e}", path.display()));
+    }
+    drop(file);
+
+    let status = run_editor(&path);
+    let content = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    status?;
+
+    let content = content.map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    Ok(parse(&content))
+}
+
+fn parse(content: &str) -> Option<(String, String)> {
+    let content = content.replace("\r\n", "\n");
+    let (name, description) = content.split_once('\n').unwrap_or((&content, ""));
+
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((
+        name.to_string(),
+        description.trim_start_matches('\n').trim_end().to_string(),
+    ))
+}
+
+// the temporary directory is usually shared, so the file must not exist yet, which also
+// rules out a symlink planted at its path, and only the user may read it
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+// $VISUAL, then $EDITOR, then vi; the variable may carry arguments, e.g. "code --wait"
+fn run_editor(path: &Path) -> Result<(), String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to start {program}: {e}"))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{program} exited with {status}, the item was not changed"
+        ))
+    }
+}
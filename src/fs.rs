@@ -1,84 +1,788 @@
 use crate::AppState;
 use crate::TodoItem;
+use crate::TodoList;
+use crate::history::History;
+use crate::priority::Priority;
+use crate::recurrence::Recurrence;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use serde::Deserialize;
 use serde::Serialize;
-use std::env::home_dir;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::fs::OpenOptions;
-use std::io::Read;
+use std::io;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const CURRENT_VERSION: u64 = 3;
+
+pub const DEFAULT_LIST_NAME: &str = "Default";
+
+// MIGRATIONS[n] upgrades a version n file to version n + 1
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] =
+    &[migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
 
 #[derive(Deserialize, Serialize)]
 struct TodoJSON {
+    version: u64,
+    // the id the next new item gets, so ids of deleted items are never handed out again
+    #[serde(default)]
+    next_id: u64,
+    lists: Vec<ListJSON>,
+    // fields written by newer versions are carried through untouched
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ListJSON {
+    name: String,
     items: Vec<JSONItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    archive: Vec<JSONItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trash: Vec<JSONItem>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
 #[derive(Deserialize, Serialize)]
 struct JSONItem {
+    #[serde(default)]
+    id: u64,
     is_done: bool,
     name: String,
     description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_date: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_time: Option<NaiveTime>,
+    #[serde(default, skip_serializing_if = "Priority::is_none")]
+    priority: Priority,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recurrence: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    completions: Vec<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    collapsed: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JSONItem>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+// file level data that is not part of any item but must survive a round trip
+#[derive(Debug, Default)]
+pub struct FileMeta {
+    version: u64,
+    next_id: u64,
+    extra: Map<String, Value>,
+    // the old version and content of a file that was upgraded when read, kept by the first save
+    pre_migration: Option<(u64, String)>,
+}
+
+impl FileMeta {
+    pub fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        id
+    }
+
+    // when overwriting another version of the file, so ids it handed out are not reused
+    pub fn reserve_ids_of(&mut self, other: &FileMeta) {
+        self.next_id = self.next_id.max(other.next_id);
+    }
+}
+
+// identifies one version of the list file, a different stamp means someone wrote to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+pub fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+
+    Some(FileStamp {
+        modified: metadata.modified().ok()?,
+        len: metadata.len(),
+    })
+}
+
+// an advisory lock on a file next to the list, released when dropped
+#[derive(Debug)]
+pub struct ListLock {
+    _file: fs::File,
+}
+
+// held for as long as an instance has the list open, None when another instance already does
+pub fn lock_session(path: &Path) -> Result<Option<ListLock>, StorageError> {
+    let lock_path = sibling(path, ".", ".lock");
+    let file = open_lock(&lock_path)?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(ListLock { _file: file })),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(StorageError::Lock(lock_path, e)),
+    }
 }
 
-pub fn read() -> Vec<TodoItem> {
-    let mut out: Vec<TodoItem> = Vec::new();
+// held around every write, waiting up to timeout for whoever is writing right now
+pub fn lock_for_write(path: &Path, timeout: Duration) -> Result<ListLock, StorageError> {
+    let lock_path = sibling(path, ".", ".write.lock");
+    let file = open_lock(&lock_path)?;
+    let start = Instant::now();
 
-    let path = home_dir().unwrap().join(".tuidolist/items.json");
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(ListLock { _file: file }),
+            Err(fs::TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err(StorageError::Locked(path.to_path_buf()));
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(StorageError::Lock(lock_path, e)),
+        }
+    }
+}
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).expect("Failed to create directory");
+fn open_lock(lock_path: &Path) -> Result<fs::File, StorageError> {
+    if let Some(parent) = lock_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| StorageError::CreateDir(parent.to_path_buf(), e))?;
     }
 
-    let mut file = OpenOptions::new()
+    OpenOptions::new()
         .create(true)
         .truncate(false)
         .write(true)
-        .read(true)
-        .open(&path)
-        .expect("Failed to create or open the file");
-
-    // write default JSON in the event of needing to create a new file
-    let mut content = String::new();
-    file.read_to_string(&mut content)
-        .expect("Failed to read file");
+        .open(lock_path)
+        .map_err(|e| StorageError::Open(lock_path.to_path_buf(), e))
+}
+
+#[derive(Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub item_count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    CreateDir(PathBuf, io::Error),
+    Open(PathBuf, io::Error),
+    Read(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Serialize(serde_json::Error),
+    Migrate(PathBuf, String),
+    Lock(PathBuf, io::Error),
+    Locked(PathBuf),
+    // the file does not exist or is empty
+    Missing(PathBuf),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::CreateDir(path, e) => {
+                write!(f, "Failed to create directory {}: {e}", path.display())
+            }
+            StorageError::Open(path, e) => write!(f, "Failed to open {}: {e}", path.display()),
+            StorageError::Read(path, e) => write!(f, "Failed to read {}: {e}", path.display()),
+            StorageError::Write(path, e) => write!(f, "Failed to write {}: {e}", path.display()),
+            StorageError::Parse(path, e) => write!(f, "Failed to parse {}: {e}", path.display()),
+            StorageError::Serialize(e) => write!(f, "Failed to serialize JSON: {e}"),
+            StorageError::Migrate(path, reason) => {
+                write!(f, "Failed to migrate {}: {reason}", path.display())
+            }
+            StorageError::Lock(path, e) => write!(f, "Failed to lock {}: {e}", path.display()),
+            StorageError::Locked(path) => {
+                write!(f, "{} is being written by another program", path.display())
+            }
+            StorageError::Missing(path) => write!(f, "{} is missing or empty", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::CreateDir(_, e)
+            | StorageError::Open(_, e)
+            | StorageError::Read(_, e)
+            | StorageError::Write(_, e)
+            | StorageError::Lock(_, e) => Some(e),
+            StorageError::Parse(_, e) | StorageError::Serialize(e) => Some(e),
+            StorageError::Migrate(..) | StorageError::Locked(_) | StorageError::Missing(_) => None,
+        }
+    }
+}
+
+pub fn read(path: &Path) -> Result<(Vec<TodoList>, FileMeta), StorageError> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| StorageError::CreateDir(parent.to_path_buf(), e))?;
+    }
+
+    // write default JSON in the event of needing to create a new file, an existing one is only read
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => file
+            .write_all(default_content().as_bytes())
+            .map_err(|e| StorageError::Write(path.to_path_buf(), e))?,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(StorageError::Open(path.to_path_buf(), e)),
+    }
+
+    let mut content =
+        fs::read_to_string(path).map_err(|e| StorageError::Read(path.to_path_buf(), e))?;
+    // an empty file is an empty list, written out on the first save
     if content.trim().is_empty() {
-        content = String::from("{\"items\":[]}");
-        file.set_len(0).expect("Failed to truncate file");
-        file.write_all(content.as_bytes())
-            .expect("Failed to write default JSON");
+        content = default_content();
     }
 
+    parse(path, &content)
+}
+
+fn default_content() -> String {
+    format!(
+        "{{\"version\":{CURRENT_VERSION},\"lists\":[{{\"name\":\"{DEFAULT_LIST_NAME}\",\"items\":[]}}]}}"
+    )
+}
+
+// the list as it is on disk, without creating or changing anything
+pub fn read_existing(path: &Path) -> Result<(Vec<TodoList>, FileMeta), StorageError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => content,
+        Ok(_) => return Err(StorageError::Missing(path.to_path_buf())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(StorageError::Missing(path.to_path_buf()));
+        }
+        Err(e) => return Err(StorageError::Read(path.to_path_buf(), e)),
+    };
+
+    parse(path, &content)
+}
+
+pub fn read_backup(path: &Path) -> Result<(Vec<TodoList>, FileMeta), StorageError> {
+    let content =
+        fs::read_to_string(path).map_err(|e| StorageError::Read(path.to_path_buf(), e))?;
+
+    // a backup is a copy already, so there is nothing to keep before saving it over the list
+    let (lists, mut file_meta) = parse(path, &content)?;
+    file_meta.pre_migration = None;
+    Ok((lists, file_meta))
+}
+
+fn parse(path: &Path, content: &str) -> Result<(Vec<TodoList>, FileMeta), StorageError> {
+    let mut value: Value =
+        serde_json::from_str(content).map_err(|e| StorageError::Parse(path.to_path_buf(), e))?;
+
+    let version = file_version(path, &value)?;
+    migrate(path, &mut value)?;
+    let (lists, mut file_meta) = from_value(path, value)?;
+
+    if version < CURRENT_VERSION {
+        file_meta.pre_migration = Some((version, content.to_string()));
+    }
+    Ok((lists, file_meta))
+}
+
+fn from_value(path: &Path, value: Value) -> Result<(Vec<TodoList>, FileMeta), StorageError> {
     let json: TodoJSON =
-        serde_json::from_str(&fs::read_to_string(&path).expect("Failed to read file"))
-            .expect("Failed to parse JSON");
+        serde_json::from_value(value).map_err(|e| StorageError::Parse(path.to_path_buf(), e))?;
+
+    let mut lists: Vec<TodoList> = json.lists.into_iter().map(list_from_json).collect();
+    let next_id = assign_missing_ids(&mut lists, json.next_id);
+
+    Ok((
+        lists,
+        FileMeta {
+            version: json.version,
+            next_id,
+            extra: json.extra,
+            pre_migration: None,
+        },
+    ))
+}
+
+// items added to the file by hand may have no id or a copied one, they get a fresh id
+// returns the id the next new item should get
+fn assign_missing_ids(lists: &mut [TodoList], next_id: u64) -> u64 {
+    let max_id = lists
+        .iter()
+        .flat_map(|list| list.items.iter().chain(&list.archive).chain(&list.trash))
+        .map(|item| item.id)
+        .max()
+        .unwrap_or(0);
 
-    json.items.iter().for_each(|item| {
+    let mut next_id = next_id.max(max_id + 1);
+    let mut seen = HashSet::new();
+    for item in lists.iter_mut().flat_map(|list| {
+        list.items
+            .iter_mut()
+            .chain(list.archive.iter_mut())
+            .chain(list.trash.iter_mut())
+    }) {
+        if item.id == 0 || !seen.insert(item.id) {
+            item.id = next_id;
+            next_id += 1;
+        }
+    }
+
+    next_id
+}
+
+fn list_from_json(list: ListJSON) -> TodoList {
+    let mut items = Vec::new();
+    flatten(list.items, 0, &mut items);
+    let mut archive = Vec::new();
+    flatten(list.archive, 0, &mut archive);
+    let mut trash = Vec::new();
+    flatten(list.trash, 0, &mut trash);
+
+    TodoList {
+        name: list.name,
+        items,
+        archive,
+        trash,
+        extra: list.extra,
+    }
+}
+
+fn lists_to_json(app_state: &AppState) -> Vec<ListJSON> {
+    app_state
+        .lists
+        .iter()
+        .enumerate()
+        .map(|(idx, list)| list_to_json(list, app_state.list_items(idx)))
+        .collect()
+}
+
+// the items of the open list live in AppState rather than in the list itself
+fn list_to_json(list: &TodoList, items: &[TodoItem]) -> ListJSON {
+    ListJSON {
+        name: list.name.clone(),
+        items: nest(items),
+        archive: nest(&list.archive),
+        trash: nest(&list.trash),
+        extra: list.extra.clone(),
+    }
+}
+
+// the file stores subtasks nested under their parent, in memory they are flat with a depth
+fn flatten(json_items: Vec<JSONItem>, depth: usize, out: &mut Vec<TodoItem>) {
+    for item in json_items {
         out.push(TodoItem {
+            id: item.id,
             is_done: item.is_done,
-            name: item.name.clone(),
-            description: item.description.clone(),
-        })
-    });
+            name: item.name,
+            description: item.description,
+            due_date: item.due_date,
+            due_time: item.due_time,
+            priority: item.priority,
+            tags: item.tags,
+            recurrence: item.recurrence,
+            completions: item.completions,
+            created_at: item.created_at,
+            modified_at: item.modified_at,
+            completed_at: item.completed_at,
+            deleted_at: item.deleted_at,
+            depth,
+            collapsed: item.collapsed,
+            extra: item.extra,
+        });
+
+        flatten(item.children, depth + 1, out);
+    }
+}
+
+fn nest(items: &[TodoItem]) -> Vec<JSONItem> {
+    crate::tree::roots(items)
+        .into_iter()
+        .map(|idx| nest_item(items, idx))
+        .collect()
+}
+
+fn nest_item(items: &[TodoItem], idx: usize) -> JSONItem {
+    let item = &items[idx];
+
+    JSONItem {
+        id: item.id,
+        is_done: item.is_done,
+        name: item.name.clone(),
+        description: item.description.clone(),
+        due_date: item.due_date,
+        due_time: item.due_time,
+        priority: item.priority,
+        tags: item.tags.clone(),
+        recurrence: item.recurrence.clone(),
+        completions: item.completions.clone(),
+        created_at: item.created_at,
+        modified_at: item.modified_at,
+        completed_at: item.completed_at,
+        deleted_at: item.deleted_at,
+        collapsed: item.collapsed,
+        children: crate::tree::children(items, idx)
+            .into_iter()
+            .map(|child| nest_item(items, child))
+            .collect(),
+        extra: item.extra.clone(),
+    }
+}
+
+// files written before versioning was introduced have no version key and count as version 0,
+// any other version that is not a whole number cannot be told apart from a damaged file
+fn file_version(path: &Path, value: &Value) -> Result<u64, StorageError> {
+    match value.get("version") {
+        None => Ok(0),
+        Some(version) => version.as_u64().ok_or_else(|| {
+            StorageError::Migrate(
+                path.to_path_buf(),
+                format!("version {version} is not a whole number"),
+            )
+        }),
+    }
+}
+
+fn migrate(path: &Path, value: &mut Value) -> Result<(), StorageError> {
+    let from = file_version(path, value)?;
+    let Value::Object(object) = value else {
+        return Err(StorageError::Migrate(
+            path.to_path_buf(),
+            String::from("top level value is not an object"),
+        ));
+    };
+
+    for version in from..CURRENT_VERSION {
+        MIGRATIONS[version as usize](object);
+        object.insert(String::from("version"), Value::from(version + 1));
+    }
+
+    Ok(())
+}
+
+fn migrate_v0_to_v1(_object: &mut Map<String, Value>) {
+    // version 1 only introduced the version key itself
+}
+
+// version 2 replaced the single item array with named lists
+fn migrate_v1_to_v2(object: &mut Map<String, Value>) {
+    // a file that has lists already only lacks the version key, and its lists must survive
+    if object.contains_key("lists") {
+        return;
+    }
 
-    out
+    let items = object
+        .remove("items")
+        .unwrap_or_else(|| Value::Array(Vec::new()));
+
+    let mut list = Map::new();
+    list.insert(String::from("name"), Value::from(DEFAULT_LIST_NAME));
+    list.insert(String::from("items"), items);
+
+    object.insert(
+        String::from("lists"),
+        Value::Array(vec![Value::Object(list)]),
+    );
 }
 
-pub fn write(app_state: &mut AppState) {
-    let path = home_dir().unwrap().join(".tuidolist/items.json");
+// version 3 gave every item, including archived and deleted ones, a stable id
+fn migrate_v2_to_v3(object: &mut Map<String, Value>) {
+    let mut next_id = 1;
 
+    if let Some(Value::Array(lists)) = object.get_mut("lists") {
+        for list in lists.iter_mut().filter_map(Value::as_object_mut) {
+            for key in ["items", "archive", "trash"] {
+                if let Some(Value::Array(items)) = list.get_mut(key) {
+                    number_items(items, &mut next_id);
+                }
+            }
+        }
+    }
+
+    object.insert(String::from("next_id"), Value::from(next_id));
+}
+
+fn number_items(items: &mut [Value], next_id: &mut u64) {
+    for item in items.iter_mut().filter_map(Value::as_object_mut) {
+        item.insert(String::from("id"), Value::from(*next_id));
+        *next_id += 1;
+
+        if let Some(Value::Array(children)) = item.get_mut("children") {
+            number_items(children, next_id);
+        }
+    }
+}
+
+// `backup` copies the file about to be overwritten to the backups first
+pub fn write(path: &Path, app_state: &AppState, backup: bool) -> Result<(), StorageError> {
     let todo_json = TodoJSON {
-        items: app_state
+        // never downgrade a file written by a newer version
+        version: app_state.file_meta.version.max(CURRENT_VERSION),
+        next_id: app_state.file_meta.next_id,
+        extra: app_state.file_meta.extra.clone(),
+        lists: lists_to_json(app_state),
+    };
+
+    let json_string = serde_json::to_string_pretty(&todo_json).map_err(StorageError::Serialize)?;
+
+    // keep the file exactly as it was before its structure was changed, only now that it is
+    // about to be overwritten, so merely looking at an old list never writes anything
+    if let Some((version, content)) = &app_state.file_meta.pre_migration {
+        let pre_migration = sibling(path, "", &format!(".v{version}.bak"));
+        if !pre_migration.exists() {
+            write_copy(path, &pre_migration, content.as_bytes())?;
+        }
+    }
+
+    // write everything to a temporary file first so a crash never leaves a truncated list behind
+    let target = resolve(path);
+    let tmp_path = sibling(&target, ".", ".tmp");
+    write_copy(&target, &tmp_path, json_string.as_bytes())?;
+
+    if backup && let Err(e) = rotate_backups(path, app_state.config.backups) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(StorageError::Write(path.to_path_buf(), e));
+    }
+
+    fs::rename(&tmp_path, &target).map_err(|e| StorageError::Write(target.clone(), e))?;
+    sync_parent(&target).map_err(|e| StorageError::Write(target, e))
+}
+
+// moving a file into place replaces a symlink rather than what it points at, so the link is
+// followed first; a file that does not exist yet stays as it is
+fn resolve(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// anything holding a copy of the list gets the list's permissions, so a private list stays private
+fn write_copy(list: &Path, path: &Path, content: &[u8]) -> Result<(), StorageError> {
+    let permissions = fs::metadata(list).ok().map(|m| m.permissions());
+
+    write_synced(path, content, permissions).map_err(|e| StorageError::Write(path.to_path_buf(), e))
+}
+
+// items.json.1 is always the most recent backup
+pub fn list_backups(path: &Path, count: usize) -> Vec<Backup> {
+    (1..=count)
+        .map(|n| backup_path(path, n))
+        .filter(|backup| backup.exists())
+        .map(|backup| Backup {
+            modified: fs::metadata(&backup).and_then(|m| m.modified()).ok(),
+            item_count: read_backup(&backup)
+                .ok()
+                .map(|(lists, _)| lists.iter().map(|list| list.items.len()).sum()),
+            path: backup,
+        })
+        .collect()
+}
+
+fn rotate_backups(path: &Path, count: usize) -> io::Result<()> {
+    if count == 0 || !path.exists() {
+        return Ok(());
+    }
+
+    for n in (1..count).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+
+    // copy rather than rename so the list file itself is never missing
+    fs::copy(path, backup_path(path, 1))?;
+
+    Ok(())
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    sibling(path, "", &format!(".{n}"))
+}
+
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let mut name = std::ffi::OsString::from(prefix);
+    name.push(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn write_synced(
+    path: &Path,
+    content: &[u8],
+    permissions: Option<fs::Permissions>,
+) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    // before writing, so the content is never readable by more people than the original
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => fs::File::open(parent)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// every list in its file form, for the undo history
+pub fn snapshot(app_state: &AppState) -> Value {
+    serde_json::to_value(lists_to_json(app_state)).unwrap_or_default()
+}
+
+// the same for lists that are not open, such as ones just read from the file
+pub fn snapshot_lists(lists: &[TodoList]) -> Value {
+    let lists: Vec<ListJSON> = lists
+        .iter()
+        .map(|list| list_to_json(list, &list.items))
+        .collect();
+    serde_json::to_value(lists).unwrap_or_default()
+}
+
+pub fn from_snapshot(snapshot: &Value) -> Option<Vec<TodoList>> {
+    let lists: Vec<ListJSON> = serde_json::from_value(snapshot.clone()).ok()?;
+    Some(lists.into_iter().map(list_from_json).collect())
+}
+
+// the undo history lives next to the list so it survives restarts
+fn journal_path(path: &Path) -> PathBuf {
+    sibling(path, ".", ".journal")
+}
+
+// a missing or unreadable journal just means starting without history
+pub fn read_journal(path: &Path) -> History {
+    fs::read_to_string(journal_path(path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_journal(path: &Path, history: &History) -> Result<(), StorageError> {
+    let journal = resolve(&journal_path(path));
+    let content = serde_json::to_string(history).map_err(StorageError::Serialize)?;
+
+    // through a temporary file like the list, so a crash never leaves half a journal behind
+    let tmp_path = sibling(&journal, "", ".tmp");
+    // it holds whole copies of the list, so nobody may read it who cannot read the list
+    write_copy(path, &tmp_path, content.as_bytes())?;
+    fs::rename(&tmp_path, &journal).map_err(|e| StorageError::Write(journal, e))
+}
+
+// keeps a copy of an unparseable list next to it before it gets overwritten
+pub fn set_aside(path: &Path) -> Result<PathBuf, StorageError> {
+    let aside = sibling(path, "", ".corrupt");
+
+    fs::copy(path, &aside).map_err(|e| StorageError::Write(aside.clone(), e))?;
+
+    Ok(aside)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every test gets its own directory, as they run in parallel
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tuidolist-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrates_version_0_to_current() {
+        let content = r#"{"items":[
+            {"is_done":false,"name":"parent","description":"",
+             "children":[{"is_done":true,"name":"child","description":""}]},
+            {"is_done":false,"name":"other","description":""}
+        ]}"#;
+
+        let (lists, file_meta) = parse(Path::new("items.json"), content).unwrap();
+
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].name, DEFAULT_LIST_NAME);
+        let items: Vec<_> = lists[0]
             .items
             .iter()
-            .map(|item| JSONItem {
-                is_done: item.is_done,
-                name: item.name.clone(),
-                description: item.description.clone(),
-            })
-            .collect(),
-    };
+            .map(|item| (item.id, item.name.as_str(), item.depth))
+            .collect();
+        assert_eq!(items, [(1, "parent", 0), (2, "child", 1), (3, "other", 0)]);
+        assert_eq!(file_meta.version, CURRENT_VERSION);
+        assert_eq!(file_meta.next_id, 4);
+        assert_eq!(file_meta.pre_migration, Some((0, content.to_string())));
+    }
+
+    #[test]
+    fn keeps_lists_of_a_file_without_version() {
+        let content = r#"{"lists":[{"name":"Work","items":[
+            {"is_done":false,"name":"kept","description":""}
+        ]}]}"#;
+
+        let (lists, _) = parse(Path::new("items.json"), content).unwrap();
 
-    let json_string = serde_json::to_string_pretty(&todo_json).expect("Failed to serialize JSON");
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].name, "Work");
+        assert_eq!(lists[0].items[0].name, "kept");
+    }
+
+    #[test]
+    fn rejects_a_version_that_is_not_a_whole_number() {
+        let content = r#"{"version":"2","lists":[{"name":"Work","items":[]}]}"#;
 
-    fs::write(&path, json_string).expect("Failed to write file");
+        assert!(matches!(
+            parse(Path::new("items.json"), content),
+            Err(StorageError::Migrate(..))
+        ));
+    }
+
+    #[test]
+    fn keeps_unknown_fields_when_saving() {
+        let path = test_dir("unknown-fields").join("items.json");
+        let content = r#"{"version":3,"next_id":3,"theme":"dark","lists":[
+            {"name":"Default","color":"red","items":[
+                {"id":1,"is_done":false,"name":"parent","description":"","estimate":3,
+                 "children":[{"id":2,"is_done":false,"name":"child","description":"",
+                              "links":["a"]}]}
+            ]}
+        ]}"#;
+        fs::write(&path, content).unwrap();
+
+        let mut app_state = AppState::default();
+        let (lists, file_meta) = read(&path).unwrap();
+        crate::load_lists(&mut app_state, lists);
+        app_state.file_meta = file_meta;
+        write(&path, &app_state, true).unwrap();
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let list = &saved["lists"][0];
+        let parent = &list["items"][0];
+        assert_eq!(saved["theme"], "dark");
+        assert_eq!(list["color"], "red");
+        assert_eq!(parent["estimate"], 3);
+        assert_eq!(parent["children"][0]["links"][0], "a");
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
 }
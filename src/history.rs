@@ -0,0 +1,53 @@
+// Undo history kept as whole snapshots of every list in their file form.
+// `current` is the last recorded state, undoing swaps it with the newest entry of `undo`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// oldest snapshots are dropped past this
+const LIMIT: usize = 50;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct History {
+    undo: Vec<Value>,
+    redo: Vec<Value>,
+    current: Value,
+}
+
+impl History {
+    // returns false when nothing changed since the last record
+    pub fn record(&mut self, snapshot: Value) -> bool {
+        if snapshot == self.current {
+            return false;
+        }
+
+        let previous = std::mem::replace(&mut self.current, snapshot);
+        if !previous.is_null() {
+            self.undo.push(previous);
+            if self.undo.len() > LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+
+        true
+    }
+
+    // the lists as last recorded
+    pub fn current(&self) -> &Value {
+        &self.current
+    }
+
+    pub fn undo(&mut self) -> Option<&Value> {
+        let previous = self.undo.pop()?;
+        self.redo
+            .push(std::mem::replace(&mut self.current, previous));
+        Some(&self.current)
+    }
+
+    pub fn redo(&mut self) -> Option<&Value> {
+        let next = self.redo.pop()?;
+        self.undo.push(std::mem::replace(&mut self.current, next));
+        Some(&self.current)
+    }
+}
@@ -0,0 +1,274 @@
+// Editable text with a cursor, shared by the text fields of the add and edit forms.
+// Only the description takes newlines; the other fields stay on a single line.
+
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::Rect,
+    prelude::Widget,
+    style::{Color, Stylize},
+    text::{Line, ToSpan},
+    widgets::{Block, Paragraph},
+};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    text: String,
+    // byte index into text, always on a grapheme boundary
+    cursor: usize,
+    // set by render_wrapped, as moving up and down depends on where long lines wrap
+    width: usize,
+    // first row shown by render_wrapped
+    scroll: usize,
+}
+
+impl TextInput {
+    // the cursor starts at the end
+    pub fn new(text: String) -> Self {
+        let cursor = text.len();
+        TextInput {
+            text,
+            cursor,
+            ..Default::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // display width of the text before the cursor on its line
+    pub fn cursor_column(&self) -> usize {
+        self.text[self.line_start()..self.cursor].width()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // returns false for keys that are not for editing, so the form can use them
+    pub fn handle_key(&mut self, k: KeyEvent) -> bool {
+        let ctrl = k.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = k.modifiers.contains(KeyModifiers::ALT);
+
+        match k.code {
+            KeyCode::Char('a') if ctrl => self.cursor = self.line_start(),
+            KeyCode::Char('e') if ctrl => self.cursor = self.line_end(),
+            KeyCode::Char('b') if alt => self.cursor = self.previous_word(),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.previous_word()),
+            KeyCode::Backspace if alt => self.delete_to(self.previous_word()),
+            KeyCode::Char('u') if ctrl => self.delete_to(self.line_start()),
+            KeyCode::Char('k') if ctrl => {
+                self.text.drain(self.cursor..self.line_end());
+            }
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.delete_to(self.previous_grapheme()),
+            KeyCode::Delete => {
+                self.text.drain(self.cursor..self.next_grapheme());
+            }
+            KeyCode::Left if ctrl => self.cursor = self.previous_word(),
+            KeyCode::Right if ctrl => self.cursor = self.next_word(),
+            KeyCode::Left => self.cursor = self.previous_grapheme(),
+            KeyCode::Right => self.cursor = self.next_grapheme(),
+            KeyCode::Home => self.cursor = self.line_start(),
+            KeyCode::End => self.cursor = self.line_end(),
+            KeyCode::Up => return self.move_rows(-1),
+            KeyCode::Down => return self.move_rows(1),
+            _ => return false,
+        }
+
+        true
+    }
+
+    // removes the text between start and the cursor
+    fn delete_to(&mut self, start: usize) {
+        self.text.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |idx| self.cursor + idx)
+    }
+
+    // graphemes rather than chars, so an accented letter or an emoji is one step
+    fn previous_grapheme(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(idx, _)| idx)
+    }
+
+    fn next_grapheme(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    // the start of the word before the cursor
+    fn previous_word(&self) -> usize {
+        self.text[..self.cursor]
+            .trim_end()
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(idx, c)| idx + c.len_utf8())
+    }
+
+    // the end of the word after the cursor
+    fn next_word(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_start().len();
+        let word = after[skipped..]
+            .find(char::is_whitespace)
+            .unwrap_or(after.len() - skipped);
+
+        self.cursor + skipped + word
+    }
+
+    // moves the cursor to the same column of another row as shown, false when there is none
+    fn move_rows(&mut self, by: isize) -> bool {
+        let rows = rows(&self.text, self.width);
+        let (row, column) = cursor_position(&self.text, &rows, self.cursor);
+        let Some(target) = row
+            .checked_add_signed(by)
+            .and_then(|target| rows.get(target))
+        else {
+            return false;
+        };
+
+        // a wrapped row ends where the next one starts, so the cursor has to stay before that
+        let is_wrapped = self.text[target.end..]
+            .chars()
+            .next()
+            .is_some_and(|c| c != '\n');
+        let row_text = &self.text[target.clone()];
+        let mut graphemes = row_text.grapheme_indices(true);
+        let last = if is_wrapped {
+            graphemes.next_back().map_or(0, |(idx, _)| idx)
+        } else {
+            row_text.len()
+        };
+
+        // the first grapheme starting at or after the column, as a wide one may straddle it
+        let mut width = 0;
+        let offset = graphemes
+            .find_map(|(idx, grapheme)| {
+                let start = width;
+                width += grapheme.width();
+                (start >= column).then_some(idx)
+            })
+            .unwrap_or(last);
+        self.cursor = target.start + offset.min(last);
+
+        true
+    }
+}
+
+// byte ranges of the rows the text is shown as, breaking lines wider than width
+pub fn rows(text: &str, width: usize) -> Vec<Range<usize>> {
+    // before the first render nothing is known about the width
+    let width = if width == 0 { usize::MAX } else { width };
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+
+    for line in text.split('\n') {
+        let mut row_start = 0;
+        let mut row_width = 0;
+
+        for (idx, grapheme) in line.grapheme_indices(true) {
+            let grapheme_width = grapheme.width();
+            if row_width > 0 && row_width + grapheme_width > width {
+                rows.push(line_start + row_start..line_start + idx);
+                row_start = idx;
+                row_width = 0;
+            }
+            row_width += grapheme_width;
+        }
+        rows.push(line_start + row_start..line_start + line.len());
+        line_start += line.len() + 1;
+    }
+
+    rows
+}
+
+// the row and column the cursor is shown at
+fn cursor_position(text: &str, rows: &[Range<usize>], cursor: usize) -> (usize, usize) {
+    // the end of a wrapped row is the start of the next, which is where the cursor goes
+    let row = rows
+        .iter()
+        .rposition(|row| row.start <= cursor)
+        .unwrap_or_default();
+
+    (row, text[rows[row].start..cursor].width())
+}
+
+// a single line inside the block, scrolled sideways to keep the cursor in view
+pub fn render_line(
+    frame: &mut Frame,
+    area: Rect,
+    input: &TextInput,
+    block: Block,
+    is_selected: bool,
+) {
+    let inner = block.inner(area);
+    // the text starts after a space
+    let column = input.cursor_column() + 1;
+    let scroll = column.saturating_sub(inner.width.saturating_sub(1) as usize);
+
+    Paragraph::new(" ".to_span() + input.text().fg(Color::default()))
+        .scroll((0, scroll as u16))
+        .block(block)
+        .render(area, frame.buffer_mut());
+
+    if is_selected && !inner.is_empty() {
+        frame.set_cursor_position((inner.x + (column - scroll) as u16, inner.y));
+    }
+}
+
+// the text broken into rows of the block's width, scrolled to keep the cursor in view
+pub fn render_wrapped(
+    frame: &mut Frame,
+    area: Rect,
+    input: &mut TextInput,
+    block: Block,
+    is_selected: bool,
+) {
+    let inner = block.inner(area);
+    input.width = inner.width.max(1) as usize;
+    let rows = rows(&input.text, input.width);
+    let (row, column) = cursor_position(&input.text, &rows, input.cursor);
+
+    let height = (inner.height as usize).max(1);
+    input.scroll = input.scroll.clamp(row.saturating_sub(height - 1), row);
+
+    Paragraph::new(
+        rows.iter()
+            .map(|range| Line::from(&input.text[range.clone()]).fg(Color::default()))
+            .collect::<Vec<_>>(),
+    )
+    .scroll((input.scroll as u16, 0))
+    .block(block)
+    .render(area, frame.buffer_mut());
+
+    if is_selected && !inner.is_empty() {
+        frame.set_cursor_position((
+            inner.x + column as u16,
+            inner.y + (row - input.scroll) as u16,
+        ));
+    }
+}
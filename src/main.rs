@@ -1,47 +1,394 @@
-use color_eyre::eyre::{Ok, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, SubsecRound};
+use clap::Parser;
+use color_eyre::eyre::Result;
+use fuzzy_matcher::skim::SkimMatcherV2;
 use ratatui::{
     DefaultTerminal, Frame,
     crossterm::event::{self, Event, KeyEvent},
     layout::{Constraint, Layout},
     prelude::Widget,
     style::{Color, Style, Stylize},
-    text::ToSpan,
-    widgets::{Block, BorderType, List, ListItem, ListState, Padding, Paragraph, Wrap},
+    text::{Line, ToSpan},
+    widgets::{Block, BorderType, List, ListItem, ListState, Padding, Paragraph, Tabs, Wrap},
 };
 
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+mod archive;
+mod cli;
+mod config;
+mod due;
+mod editor;
 mod fs;
+mod history;
+mod input;
+mod priority;
+mod recurrence;
+mod search;
+mod tags;
+mod text;
+mod trash;
+mod tree;
+
+use crate::input::TextInput;
+use crate::priority::Priority;
+use crate::recurrence::Recurrence;
+
+/// A simple terminal based to do list
+#[derive(Debug, Parser)]
+#[command(name = "tuidolist", version, about)]
+struct Args {
+    /// Path to the list file (overrides $TUIDOLIST_FILE and the config file)
+    #[arg(long, value_name = "PATH", global = true)]
+    file: Option<PathBuf>,
+    /// List that subcommands work on (defaults to the first list)
+    #[arg(long, value_name = "NAME", global = true)]
+    list: Option<String>,
+    /// Run a single command instead of starting the interface
+    #[command(subcommand)]
+    command: Option<crate::cli::Command>,
+}
 
 #[derive(Debug, Default)]
 pub struct AppState {
+    file_path: PathBuf,
+    config: crate::config::Config,
+    file_meta: crate::fs::FileMeta,
+    lists: Vec<TodoList>,
+    // the open list, whose items are held in `items` while its own vector stays empty
+    current_list: usize,
     items: Vec<TodoItem>,
     list_state: ListState,
     is_add_new: bool,
     is_deleting: bool,
     is_open: bool,
+    // first description row shown in the open item, clamped when rendered
+    item_scroll: usize,
+    // picked up by run, which hands the terminal over to the editor
+    editor_request: Option<EditorRequest>,
     is_editing: bool,
     is_moving: bool,
+    is_completing_subtasks: bool,
+    // set while adding a subtask to this item
+    add_parent: Option<usize>,
+    is_restoring: bool,
+    sort_mode: SortMode,
+    is_filtering_tags: bool,
+    tag_filter: Vec<String>,
+    // true to show items with all of the filter tags, false for any of them
+    tag_filter_all: bool,
+    tag_list_state: ListState,
+    is_creating_list: bool,
+    is_renaming_list: bool,
+    list_name_input: String,
+    is_deleting_list: bool,
+    // picking the list to move the selected item to
+    is_moving_to_list: bool,
+    list_picker_state: ListState,
+    is_searching: bool,
+    search_query: String,
+    // the item selected when the search was started, returned to when it is cancelled
+    search_origin: Option<usize>,
+    is_finding: bool,
+    finder_query: String,
+    finder_list_state: ListState,
+    hide_done: bool,
+    is_viewing_archive: bool,
+    archive_list_state: ListState,
+    is_viewing_trash: bool,
+    trash_list_state: ListState,
+    is_viewing_help: bool,
+    history: crate::history::History,
+    // the version of the list file last read or written, to notice changes made by others
+    file_stamp: Option<crate::fs::FileStamp>,
+    // the lists as they were last read or written
+    saved_snapshot: serde_json::Value,
+    save_state: SaveState,
+    // when the lists last changed, the autosave waits for a pause in editing
+    last_change: Option<Instant>,
+    // saves put off because the list file was empty, as another program may be writing it
+    empty_file_retries: u32,
+    // when the list file was last copied to the backups, none yet means the next save does
+    last_backup: Option<Instant>,
+    // when the undo history was last written, and whether it changed since
+    last_journal: Option<Instant>,
+    journal_pending: bool,
+    // the file's contents after another program changed it while there were unsaved changes
+    conflict: Option<(Vec<TodoList>, crate::fs::FileMeta)>,
+    // keeps a second instance from opening the list read-write unasked
+    session_lock: Option<crate::fs::ListLock>,
+    // another instance has the list open and the user has not yet chosen what to do
+    is_locked: bool,
+    // changes are put back instead of saved
+    read_only: bool,
+    backups: Vec<crate::fs::Backup>,
+    backup_list_state: ListState,
     input_state: InputState,
+    load_error: Option<String>,
+    error_message: Option<String>,
+}
+
+impl AppState {
+    // the open list's items live in `items` rather than in its slot of `lists`
+    fn list_items(&self, list: usize) -> &[TodoItem] {
+        if list == self.current_list {
+            &self.items
+        } else {
+            &self.lists[list].items
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 struct InputState {
-    name_input: String,
-    description_input: String,
+    name_input: TextInput,
+    description_input: TextInput,
+    due_input: TextInput,
+    priority: Priority,
+    tags_input: TextInput,
+    recurrence_input: TextInput,
     select_state: InputSelectState,
 }
 
+impl InputState {
+    fn from_item(item: &TodoItem) -> Self {
+        InputState {
+            name_input: TextInput::new(item.name.clone()),
+            description_input: TextInput::new(item.description.clone()),
+            due_input: TextInput::new(
+                item.due_date
+                    .map(|date| crate::due::format(date, item.due_time))
+                    .unwrap_or_default(),
+            ),
+            priority: item.priority,
+            tags_input: TextInput::new(crate::tags::format(&item.tags)),
+            recurrence_input: TextInput::new(
+                item.recurrence
+                    .as_ref()
+                    .map(Recurrence::to_string)
+                    .unwrap_or_default(),
+            ),
+            select_state: InputSelectState::default(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name_input.text().trim().is_empty() {
+            return Err(String::from("Name is empty"));
+        }
+        crate::due::parse(self.due_input.text())?;
+        Recurrence::parse(self.recurrence_input.text())?;
+
+        Ok(())
+    }
+
+    // writes every field into the item, the input must be valid
+    fn apply(&self, item: &mut TodoItem) {
+        let due = crate::due::parse(self.due_input.text()).ok().flatten();
+
+        item.name = self.name_input.text().to_string();
+        item.description = self.description_input.text().to_string();
+        item.due_date = due.map(|(date, _)| date);
+        item.due_time = due.and_then(|(_, time)| time);
+        item.priority = self.priority;
+        item.tags = crate::tags::parse(self.tags_input.text());
+        item.recurrence = Recurrence::parse(self.recurrence_input.text())
+            .ok()
+            .flatten();
+        item.modified_at = Some(now());
+    }
+
+    // the priority field is a selector rather than free text
+    fn selected_input(&mut self) -> Option<&mut TextInput> {
+        match self.select_state {
+            InputSelectState::Name => Some(&mut self.name_input),
+            InputSelectState::Description => Some(&mut self.description_input),
+            InputSelectState::Due => Some(&mut self.due_input),
+            InputSelectState::Priority => None,
+            InputSelectState::Tags => Some(&mut self.tags_input),
+            InputSelectState::Recurrence => Some(&mut self.recurrence_input),
+        }
+    }
+}
+
 #[derive(Debug, Default, PartialEq)]
 enum InputSelectState {
     #[default]
     Name,
     Description,
+    Due,
+    Priority,
+    Tags,
+    Recurrence,
+}
+
+impl InputSelectState {
+    fn next(&self) -> Self {
+        match self {
+            InputSelectState::Name => InputSelectState::Description,
+            InputSelectState::Description => InputSelectState::Due,
+            InputSelectState::Due => InputSelectState::Priority,
+            InputSelectState::Priority => InputSelectState::Tags,
+            InputSelectState::Tags => InputSelectState::Recurrence,
+            InputSelectState::Recurrence => InputSelectState::Name,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TodoList {
+    name: String,
+    items: Vec<TodoItem>,
+    // completed items moved out of the list, see the archive module
+    archive: Vec<TodoItem>,
+    // deleted items, see the trash module
+    trash: Vec<TodoItem>,
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Default)]
 struct TodoItem {
+    // unique within the file and kept across moves, lists and restarts
+    id: u64,
     is_done: bool,
     name: String,
     description: String,
+    due_date: Option<NaiveDate>,
+    due_time: Option<NaiveTime>,
+    priority: Priority,
+    tags: Vec<String>,
+    recurrence: Option<Recurrence>,
+    // when a recurring item was completed, oldest first
+    completions: Vec<NaiveDateTime>,
+    // missing on items created before timestamps were recorded
+    created_at: Option<NaiveDateTime>,
+    modified_at: Option<NaiveDateTime>,
+    completed_at: Option<NaiveDateTime>,
+    // only set on the top item of a deleted subtree while it is in the trash
+    deleted_at: Option<NaiveDateTime>,
+    // level in the tree, see the tree module
+    depth: usize,
+    collapsed: bool,
+    // fields from newer versions of the file format, kept so saving does not drop them
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+// whether the lists on screen have been written to the list file
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum SaveState {
+    #[default]
+    Saved,
+    Unsaved,
+    // shown for one frame before the write, which can wait on another program's lock
+    Saving,
+    // kept until the next change or quitting tries again
+    Failed,
+}
+
+// every mode other than manual only changes the order items are shown in
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum SortMode {
+    #[default]
+    Manual,
+    Priority,
+    Created,
+    Modified,
+    Completed,
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::Manual => SortMode::Priority,
+            SortMode::Priority => SortMode::Created,
+            SortMode::Created => SortMode::Modified,
+            SortMode::Modified => SortMode::Completed,
+            SortMode::Completed => SortMode::Manual,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Manual => "Manual",
+            SortMode::Priority => "Priority",
+            SortMode::Created => "Created",
+            SortMode::Modified => "Modified",
+            SortMode::Completed => "Completed",
+        }
+    }
+}
+
+impl TodoItem {
+    fn set_done(&mut self, is_done: bool) {
+        let now = now();
+
+        self.is_done = is_done;
+        self.completed_at = is_done.then_some(now);
+        self.modified_at = Some(now);
+    }
+}
+
+// every key of the main view, listed by the help popup
+const HELP: &[(&str, &str)] = &[
+    ("j/k", "Down / up"),
+    ("Enter", "Open item"),
+    ("a", "New item"),
+    ("A", "New subtask"),
+    ("e", "Edit item"),
+    ("E", "Edit in editor"),
+    ("d", "Delete item"),
+    ("c", "Complete item"),
+    ("m", "Move item"),
+    ("h/l", "Collapse / expand"),
+    ("+/-", "Raise / lower priority"),
+    ("s", "Sort"),
+    ("u/C-r", "Undo / redo"),
+    ("b", "Backups"),
+    ("/", "Search"),
+    ("n/N", "Next / previous match"),
+    ("f", "Find in all lists"),
+    ("t", "Filter by tag"),
+    ("H", "Hide done items"),
+    ("C/V", "Archive done / view"),
+    ("T", "Trash"),
+    ("Tab/S-Tab", "Next / previous list"),
+    ("L/R/X", "New / rename / delete list"),
+    ("M", "Move item to list"),
+    ("?", "Keys"),
+    ("Esc", "Exit"),
+];
+
+// the margins, the title, due and tags rows, and a description box with three lines of text
+const FORM_MIN_HEIGHT: u16 = 18;
+
+// wide enough for the key hints at the bottom of the forms
+const FORM_MIN_WIDTH: u16 = 56;
+
+// names in the list are cut with an ellipsis, but never shorter than this
+const MIN_NAME_WIDTH: usize = 10;
+
+// how long the lists have to stay unchanged before they are saved
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+
+// an empty list file is only saved over once it stayed empty for this many saves, a delay apart
+const EMPTY_FILE_RETRIES: u32 = 3;
+
+// the list file is backed up on the first save and then at most this often, so a burst of
+// small edits cannot push every older version out of the backups
+const BACKUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// the undo history holds whole copies of the lists, so it is written at most this often and
+// once more when quitting
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(60);
+
+// how often the list file is looked at for changes made by others
+const TICK: Duration = Duration::from_millis(500);
+
+const READ_ONLY_MESSAGE: &str = "The list is open read-only, changes are not saved";
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local().trunc_subsecs(0)
 }
 
 enum FormAction {
@@ -50,48 +397,189 @@ enum FormAction {
     Escape,
 }
 
+#[derive(Debug)]
+enum EditorRequest {
+    // the item at this index, changed as soon as the editor closes
+    Item(usize),
+    // the title and description in the open add or edit form
+    Form,
+}
+
 fn main() -> Result<()> {
+    let args = Args::parse();
     let mut state = AppState::default();
     color_eyre::install()?;
 
-    state.items = crate::fs::read();
+    // the defaults are used rather than refusing to start, but never without saying so
+    let config_error = match crate::config::load() {
+        Ok(config) => {
+            state.config = config;
+            None
+        }
+        Err(e) => Some(format!("{e}, using the default settings")),
+    };
+    state.file_path = crate::config::resolve_list_path(args.file, &state.config);
+    if let Some(command) = args.command {
+        if let Some(e) = &config_error {
+            eprintln!("warning: {e}");
+        }
+        // scripts get a plain message and exit code instead of a report
+        if let Err(e) = crate::cli::run(command, args.list, &mut state) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let lock_error = match crate::fs::lock_session(&state.file_path) {
+        Ok(Some(lock)) => {
+            state.session_lock = Some(lock);
+            None
+        }
+        // nothing is written until the user picks read-only or take over
+        Ok(None) => {
+            state.is_locked = true;
+            state.read_only = true;
+            None
+        }
+        // e.g. a list in a directory that cannot be written, which can still be looked at
+        Err(e) => {
+            state.read_only = true;
+            Some(format!("{e}, the list is open read-only"))
+        }
+    };
+    state.error_message = [config_error, lock_error]
+        .into_iter()
+        .flatten()
+        .reduce(|a, b| format!("{a}\n{b}"));
+
+    state.history = crate::fs::read_journal(&state.file_path);
+    let result = crate::fs::read(&state.file_path);
+    state.file_stamp = crate::fs::stamp(&state.file_path);
+    match result {
+        Ok((mut lists, file_meta)) => {
+            let archived = state
+                .config
+                .auto_archive_days
+                .filter(|_| !state.read_only)
+                .map_or(0, |days| {
+                    crate::archive::archive_older_than(&mut lists, now() - chrono::Days::new(days))
+                });
+            let purged = state
+                .config
+                .trash_retention_days
+                .filter(|_| !state.read_only)
+                .map_or(0, |days| {
+                    crate::trash::purge_older_than(&mut lists, now() - chrono::Days::new(days))
+                });
+
+            load_lists(&mut state, lists);
+            state.file_meta = file_meta;
+            state.saved_snapshot = crate::fs::snapshot(&state);
+            if archived > 0 || purged > 0 {
+                save(&mut state);
+            } else {
+                // picks up changes made to the file since the journal was last written
+                record_history(&mut state);
+            }
+        }
+        Err(e @ (crate::fs::StorageError::Parse(..) | crate::fs::StorageError::Migrate(..))) => {
+            state.load_error = Some(e.to_string())
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let (terminal, enhanced) = init_terminal();
+    let res = run(terminal, enhanced, &mut state);
+
+    restore_terminal(enhanced);
+    res
+}
 
+fn init_terminal() -> (DefaultTerminal, bool) {
     let terminal = ratatui::init();
-    let res = run(terminal, &mut state);
+    // lets terminals that support it report Shift-Enter, which inserts newlines in descriptions
+    let enhanced = ratatui::crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false)
+        && ratatui::crossterm::execute!(
+            std::io::stdout(),
+            event::PushKeyboardEnhancementFlags(
+                event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+            )
+        )
+        .is_ok();
+
+    (terminal, enhanced)
+}
 
+fn restore_terminal(enhanced: bool) {
+    if enhanced {
+        let _ = ratatui::crossterm::execute!(std::io::stdout(), event::PopKeyboardEnhancementFlags);
+    }
     ratatui::restore();
-    res
 }
 
-fn run(mut terminal: DefaultTerminal, app_state: &mut AppState) -> Result<()> {
+fn run(mut terminal: DefaultTerminal, enhanced: bool, app_state: &mut AppState) -> Result<()> {
     loop {
+        autosave(app_state);
+        check_external_change(app_state);
         //Redering
         terminal.draw(|f| render(f, app_state))?;
+        // wake up now and then to save and to look at the list file even without input
+        if !event::poll(tick(app_state))? {
+            continue;
+        }
         //Input handling
         if let Event::Key(k) = event::read()? {
-            if app_state.is_add_new {
+            if app_state.error_message.is_some() {
+                handle_error(k, app_state);
+            } else if app_state.is_locked {
+                if handle_locked(k, app_state) && can_quit(app_state) {
+                    break;
+                }
+            } else if app_state.conflict.is_some() {
+                handle_conflict(k, app_state);
+            } else if app_state.is_restoring {
+                handle_restore(k, app_state);
+            } else if app_state.load_error.is_some() {
+                if handle_load_error(k, app_state) && can_quit(app_state) {
+                    break;
+                }
+            } else if app_state.is_filtering_tags {
+                handle_tag_filter(k, app_state);
+            } else if app_state.is_searching {
+                handle_search(k, app_state);
+            } else if app_state.is_finding {
+                handle_finder(k, app_state);
+            } else if app_state.is_viewing_help {
+                handle_help(k, app_state);
+            } else if app_state.is_viewing_archive {
+                handle_archive(k, app_state);
+            } else if app_state.is_viewing_trash {
+                handle_trash(k, app_state);
+            } else if app_state.is_creating_list || app_state.is_renaming_list {
+                handle_list_name(k, app_state);
+            } else if app_state.is_deleting_list {
+                handle_delete_list(k, app_state);
+            } else if app_state.is_moving_to_list {
+                handle_move_to_list(k, app_state);
+            } else if app_state.is_add_new {
                 match handle_add_new(k, app_state) {
                     FormAction::None => {}
                     FormAction::Submit => {
                         app_state.is_add_new = false;
 
-                        app_state.items.push(TodoItem {
-                            is_done: false,
-                            name: app_state.input_state.name_input.clone(),
-                            description: app_state.input_state.description_input.clone(),
-                        });
+                        let parent = app_state.add_parent.take();
+                        let new_idx = add_item(app_state, parent);
+                        select_index(app_state, new_idx);
 
-                        app_state.input_state.name_input.clear();
-                        app_state.input_state.description_input.clear();
-                        app_state.input_state.select_state = InputSelectState::Name;
+                        app_state.input_state = InputState::default();
 
-                        crate::fs::write(app_state);
+                        mark_dirty(app_state);
                     }
                     FormAction::Escape => {
                         app_state.is_add_new = false;
-                        app_state.input_state.name_input.clear();
-                        app_state.input_state.description_input.clear();
-                        app_state.input_state.select_state = InputSelectState::Name;
+                        app_state.add_parent = None;
+                        app_state.input_state = InputState::default();
                     }
                 }
             } else if app_state.is_editing {
@@ -100,221 +588,1492 @@ fn run(mut terminal: DefaultTerminal, app_state: &mut AppState) -> Result<()> {
                     FormAction::Submit => {
                         app_state.is_editing = false;
 
-                        if let Some(item) = app_state
-                            .list_state
-                            .selected()
-                            .and_then(|idx| app_state.items.get_mut(idx))
-                        {
-                            item.name = app_state.input_state.name_input.clone();
-                            item.description = app_state.input_state.description_input.clone();
+                        if let Some(idx) = selected_index(app_state) {
+                            app_state.input_state.apply(&mut app_state.items[idx]);
                         }
 
-                        app_state.input_state.name_input.clear();
-                        app_state.input_state.description_input.clear();
-                        app_state.input_state.select_state = InputSelectState::Name;
+                        app_state.input_state = InputState::default();
 
-                        crate::fs::write(app_state);
+                        mark_dirty(app_state);
                     }
                     FormAction::Escape => {
                         app_state.is_editing = false;
-                        app_state.input_state.name_input.clear();
-                        app_state.input_state.description_input.clear();
-                        app_state.input_state.select_state = InputSelectState::Name;
+                        app_state.input_state = InputState::default();
                     }
                 }
+            } else if app_state.is_completing_subtasks {
+                handle_complete_subtasks(k, app_state);
             } else if app_state.is_deleting {
                 handle_delete(k, app_state);
             } else if app_state.is_open {
                 handle_open(k, app_state);
             } else if app_state.is_moving {
                 handle_move(k, app_state);
-            } else if handle_key(k, app_state) {
+            } else if handle_key(k, app_state) && can_quit(app_state) {
                 break;
             }
         }
+
+        if let Some(request) = app_state.editor_request.take() {
+            restore_terminal(enhanced);
+            open_editor(app_state, request);
+            terminal = init_terminal().0;
+        }
+    }
+
+    // the undo history of the last changes is still to be written
+    if app_state.journal_pending {
+        crate::fs::write_journal(&app_state.file_path, &app_state.history)?;
     }
     Ok(())
 }
 
-fn handle_open(k: KeyEvent, app_state: &mut AppState) -> bool {
-    if k.code == event::KeyCode::Esc {
-        app_state.is_open = false;
+// runs the editor on the terminal the TUI has just given up
+fn open_editor(app_state: &mut AppState, request: EditorRequest) {
+    let (name, description) = match request {
+        EditorRequest::Item(idx) => {
+            let item = &app_state.items[idx];
+            (item.name.clone(), item.description.clone())
+        }
+        EditorRequest::Form => (
+            app_state.input_state.name_input.text().to_string(),
+            app_state.input_state.description_input.text().to_string(),
+        ),
+    };
+
+    match crate::editor::edit(&name, &description) {
+        Ok(Some(edited)) if edited != (name, description) => match request {
+            EditorRequest::Item(idx) => {
+                let item = &mut app_state.items[idx];
+                (item.name, item.description) = edited;
+                item.modified_at = Some(now());
+                mark_dirty(app_state);
+            }
+            EditorRequest::Form => {
+                let (name, description) = edited;
+                app_state.input_state.name_input = TextInput::new(name);
+                app_state.input_state.description_input = TextInput::new(description);
+            }
+        },
+        Ok(_) => {}
+        Err(e) => {
+            app_state.error_message = Some(e);
+        }
     }
+}
 
-    false
+// adds a new item from the input state, as the last subtask of parent if given
+fn add_item(app_state: &mut AppState, parent: Option<usize>) -> usize {
+    let mut new_item = TodoItem {
+        id: app_state.file_meta.allocate_id(),
+        created_at: Some(now()),
+        ..Default::default()
+    };
+    app_state.input_state.apply(&mut new_item);
+
+    match parent {
+        Some(parent) => {
+            let idx = crate::tree::subtree_end(&app_state.items, parent);
+            app_state.items[parent].collapsed = false;
+            app_state.items.insert(
+                idx,
+                TodoItem {
+                    depth: app_state.items[parent].depth + 1,
+                    ..new_item
+                },
+            );
+            idx
+        }
+        None => {
+            app_state.items.push(new_item);
+            app_state.items.len() - 1
+        }
+    }
 }
 
-fn handle_add_new(k: KeyEvent, app_state: &mut AppState) -> FormAction {
-    match k.code {
-        event::KeyCode::Char(c) => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.name_input.push(c)
-            } else {
-                app_state.input_state.description_input.push(c)
-            };
+// called after every change to the lists, autosave writes them out once editing pauses
+fn mark_dirty(app_state: &mut AppState) {
+    record_history(app_state);
+    if app_state.read_only {
+        return;
+    }
+
+    // e.g. leaving move mode without moving anything, or undoing back to what was saved
+    if *app_state.history.current() == app_state.saved_snapshot {
+        app_state.save_state = SaveState::Saved;
+    } else {
+        app_state.save_state = SaveState::Unsaved;
+        app_state.last_change = Some(Instant::now());
+    }
+}
+
+// in two steps, so the status shows the save in progress before it blocks
+fn autosave(app_state: &mut AppState) {
+    // an open form or popup may be holding on to the selected item, so wait until it is closed
+    if !is_idle(app_state) {
+        if app_state.save_state == SaveState::Saving {
+            app_state.save_state = SaveState::Unsaved;
         }
-        event::KeyCode::Backspace => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.name_input.pop()
-            } else {
-                app_state.input_state.description_input.pop()
-            };
+        return;
+    }
+
+    match app_state.save_state {
+        SaveState::Saving => save(app_state),
+        SaveState::Unsaved
+            if app_state.conflict.is_none()
+                && app_state
+                    .last_change
+                    .is_some_and(|time| time.elapsed() >= AUTOSAVE_DELAY) =>
+        {
+            app_state.save_state = SaveState::Saving;
         }
-        event::KeyCode::Enter => {
-            if app_state.input_state.name_input.is_empty() {
-                return FormAction::None;
-            } else {
-                return FormAction::Submit;
-            }
+        _ => {}
+    }
+}
+
+// how long to wait for input before the next autosave or look at the list file
+fn tick(app_state: &AppState) -> Duration {
+    if !is_idle(app_state) {
+        return TICK;
+    }
+
+    match (app_state.save_state, app_state.last_change) {
+        (SaveState::Saving, _) => Duration::ZERO,
+        (SaveState::Unsaved, Some(time)) => AUTOSAVE_DELAY.saturating_sub(time.elapsed()).min(TICK),
+        _ => TICK,
+    }
+}
+
+// saves before quitting, and stays open when that would lose changes without a word
+fn can_quit(app_state: &mut AppState) -> bool {
+    if app_state.save_state == SaveState::Saved {
+        return true;
+    }
+
+    // the failure was already shown, so a second failed attempt gives up on the changes
+    let failed_before = app_state.save_state == SaveState::Failed;
+    save(app_state);
+
+    // the save waits for an empty list file to be written, which quitting again does not
+    if app_state.save_state == SaveState::Unsaved && app_state.conflict.is_none() {
+        app_state.empty_file_retries = EMPTY_FILE_RETRIES;
+        app_state.error_message = Some(format!(
+            "{} is empty, another program may be writing it. Quit again to save over it",
+            app_state.file_path.display()
+        ));
+        return false;
+    }
+
+    app_state.save_state == SaveState::Saved || (failed_before && app_state.conflict.is_none())
+}
+
+fn save(app_state: &mut AppState) {
+    if app_state.read_only {
+        discard_changes(app_state);
+        return;
+    }
+    record_history(app_state);
+
+    // nobody else can write between looking at the file and writing it
+    let _lock = match crate::fs::lock_for_write(&app_state.file_path, Duration::from_secs(1)) {
+        Ok(lock) => lock,
+        Err(e) => {
+            app_state.save_state = SaveState::Failed;
+            app_state.error_message = Some(e.to_string());
+            return;
         }
-        event::KeyCode::Esc => {
-            return FormAction::Escape;
+    };
+    let stamp = crate::fs::stamp(&app_state.file_path);
+    if stamp.is_some() && stamp != app_state.file_stamp {
+        match crate::fs::read_existing(&app_state.file_path) {
+            // another program wrote since the file was last read, writing now would lose that
+            Ok((lists, file_meta)) => {
+                app_state.file_stamp = stamp;
+                app_state.conflict = Some((lists, file_meta));
+                app_state.save_state = SaveState::Unsaved;
+                return;
+            }
+            // emptied by a write still in progress, so the save is tried again a moment later;
+            // a file that stays empty holds nothing to lose and is saved over
+            Err(crate::fs::StorageError::Missing(_))
+                if app_state.empty_file_retries < EMPTY_FILE_RETRIES =>
+            {
+                app_state.empty_file_retries += 1;
+                app_state.save_state = SaveState::Unsaved;
+                app_state.last_change = Some(Instant::now());
+                return;
+            }
+            // a list that cannot be read is kept among the backups when it is overwritten
+            Err(_) => app_state.last_backup = None,
         }
-        event::KeyCode::Tab => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.select_state = InputSelectState::Description
-            } else {
-                app_state.input_state.select_state = InputSelectState::Name
+    }
+
+    let backup = app_state
+        .last_backup
+        .is_none_or(|time| time.elapsed() >= BACKUP_INTERVAL);
+    match crate::fs::write(&app_state.file_path, app_state, backup) {
+        Ok(()) => {
+            app_state.empty_file_retries = 0;
+            app_state.file_stamp = crate::fs::stamp(&app_state.file_path);
+            app_state.saved_snapshot = crate::fs::snapshot(app_state);
+            app_state.save_state = SaveState::Saved;
+            if backup {
+                app_state.last_backup = Some(Instant::now());
+            }
+            // the whole history is large, so it is only written along with the list
+            app_state.journal_pending = true;
+            if app_state
+                .last_journal
+                .is_none_or(|time| time.elapsed() >= JOURNAL_INTERVAL)
+            {
+                write_journal(app_state);
             }
         }
-        _ => {}
+        Err(e) => {
+            app_state.save_state = SaveState::Failed;
+            app_state.error_message = Some(e.to_string());
+        }
     }
+}
 
-    FormAction::None
+// only look at the file from the main view, so nothing shifts under an open form or popup
+fn is_idle(app_state: &AppState) -> bool {
+    ![
+        app_state.is_add_new,
+        app_state.is_deleting,
+        app_state.is_open,
+        app_state.is_editing,
+        app_state.is_moving,
+        app_state.is_completing_subtasks,
+        app_state.is_restoring,
+        app_state.is_filtering_tags,
+        app_state.is_creating_list,
+        app_state.is_renaming_list,
+        app_state.is_deleting_list,
+        app_state.is_moving_to_list,
+        app_state.is_searching,
+        app_state.is_finding,
+        app_state.is_viewing_archive,
+        app_state.is_viewing_trash,
+        app_state.load_error.is_some(),
+        app_state.error_message.is_some(),
+        app_state.conflict.is_some(),
+        app_state.is_locked,
+    ]
+    .contains(&true)
 }
 
-fn handle_edit(k: KeyEvent, app_state: &mut AppState) -> FormAction {
+// reloads the list file after another program changed it, or asks when that would lose changes
+fn check_external_change(app_state: &mut AppState) {
+    if !is_idle(app_state) {
+        return;
+    }
+
+    let stamp = crate::fs::stamp(&app_state.file_path);
+    if stamp.is_none() || stamp == app_state.file_stamp {
+        return;
+    }
+
+    // an empty or unreadable file is most likely a write still in progress, so it is retried
+    // on the next tick, and nothing is written to it here
+    if let Ok((lists, file_meta)) = crate::fs::read_existing(&app_state.file_path) {
+        app_state.file_stamp = stamp;
+
+        if app_state.save_state == SaveState::Saved {
+            replace_lists(app_state, lists, file_meta);
+        } else {
+            app_state.conflict = Some((lists, file_meta));
+        }
+    }
+}
+
+// swaps in lists read from the file
+fn replace_lists(
+    app_state: &mut AppState,
+    lists: Vec<TodoList>,
+    mut file_meta: crate::fs::FileMeta,
+) {
+    apply_lists(app_state, lists);
+    // ids handed out here may still be brought back by undo, so they are never given out again
+    file_meta.reserve_ids_of(&app_state.file_meta);
+    app_state.file_meta = file_meta;
+
+    app_state.saved_snapshot = crate::fs::snapshot(app_state);
+    app_state.save_state = SaveState::Saved;
+    record_history(app_state);
+}
+
+// puts back the lists as they were last read, as nothing may be saved in read-only mode
+fn discard_changes(app_state: &mut AppState) {
+    if crate::fs::snapshot(app_state) == app_state.saved_snapshot {
+        return;
+    }
+
+    if let Some(lists) = crate::fs::from_snapshot(&app_state.saved_snapshot) {
+        apply_lists(app_state, lists);
+    }
+    app_state.error_message = Some(String::from(READ_ONLY_MESSAGE));
+}
+
+// loads the lists, staying on the same list and item where they still exist
+fn apply_lists(app_state: &mut AppState, lists: Vec<TodoList>) {
+    let list_name = app_state.lists[app_state.current_list].name.clone();
+    let selected_id = selected_index(app_state).map(|idx| app_state.items[idx].id);
+
+    load_lists(app_state, lists);
+    if let Some(list) = app_state
+        .lists
+        .iter()
+        .position(|list| list.name == list_name)
+        .filter(|&list| list != 0)
+    {
+        switch_list(app_state, list);
+    }
+
+    let selected = selected_id.and_then(|id| app_state.items.iter().position(|item| item.id == id));
+    reselect(app_state, selected);
+}
+
+fn handle_locked(k: KeyEvent, app_state: &mut AppState) -> bool {
     match k.code {
-        event::KeyCode::Char(c) => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.name_input.push(c)
-            } else {
-                app_state.input_state.description_input.push(c)
-            };
+        event::KeyCode::Char('r') => {
+            app_state.is_locked = false;
         }
-        event::KeyCode::Backspace => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.name_input.pop()
-            } else {
-                app_state.input_state.description_input.pop()
-            };
+        event::KeyCode::Char('t') => {
+            app_state.is_locked = false;
+            app_state.read_only = false;
+            // the other instance may have quit in the meantime
+            app_state.session_lock = crate::fs::lock_session(&app_state.file_path).ok().flatten();
+            record_history(app_state);
         }
-        event::KeyCode::Enter => {
-            if app_state.input_state.name_input.is_empty() {
-                return FormAction::None;
-            } else {
-                return FormAction::Submit;
-            }
+        event::KeyCode::Char('q') | event::KeyCode::Esc => {
+            return true;
         }
-        event::KeyCode::Esc => {
-            return FormAction::Escape;
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_conflict(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Char('m') => {
+            if let Some((lists, file_meta)) = app_state.conflict.take() {
+                app_state.file_meta.reserve_ids_of(&file_meta);
+                // the version about to be overwritten was never backed up
+                app_state.last_backup = None;
+                // so the version on disk can still be brought back with undo once overwritten
+                app_state.history.record(crate::fs::snapshot_lists(&lists));
+                save(app_state);
+            }
         }
-        event::KeyCode::Tab => {
-            if app_state.input_state.select_state == InputSelectState::Name {
-                app_state.input_state.select_state = InputSelectState::Description
-            } else {
-                app_state.input_state.select_state = InputSelectState::Name
+        event::KeyCode::Char('t') => {
+            if let Some((lists, file_meta)) = app_state.conflict.take() {
+                replace_lists(app_state, lists, file_meta);
             }
         }
         _ => {}
     }
 
-    FormAction::None
+    false
 }
 
-fn handle_delete(k: KeyEvent, app_state: &mut AppState) -> bool {
-    if let event::KeyCode::Char(c) = k.code {
-        match c {
-            'y' => {
-                if let Some(idx) = app_state.list_state.selected() {
-                    app_state.items.remove(idx);
+// adds the previous state to the undo history if the lists changed since the last record
+fn record_history(app_state: &mut AppState) {
+    // the journal belongs to the instance that can save
+    if app_state.read_only {
+        discard_changes(app_state);
+        return;
+    }
 
-                    crate::fs::write(app_state);
-                }
-                app_state.is_deleting = false;
-            }
-            'n' => {
-                app_state.is_deleting = false;
+    app_state.history.record(crate::fs::snapshot(app_state));
+}
+
+fn write_journal(app_state: &mut AppState) {
+    match crate::fs::write_journal(&app_state.file_path, &app_state.history) {
+        Ok(()) => {
+            app_state.last_journal = Some(Instant::now());
+            app_state.journal_pending = false;
+        }
+        Err(e) => app_state.error_message = Some(e.to_string()),
+    }
+}
+
+fn undo(app_state: &mut AppState, redo: bool) {
+    if app_state.read_only {
+        app_state.error_message = Some(String::from(READ_ONLY_MESSAGE));
+        return;
+    }
+
+    let snapshot = if redo {
+        app_state.history.redo()
+    } else {
+        app_state.history.undo()
+    };
+
+    match snapshot.and_then(crate::fs::from_snapshot) {
+        Some(lists) => {
+            let selected = selected_index(app_state);
+            let current = app_state.current_list.min(lists.len().saturating_sub(1));
+
+            load_lists(app_state, lists);
+            if current != 0 {
+                switch_list(app_state, current);
             }
-            _ => {}
+            reselect(app_state, selected);
+
+            mark_dirty(app_state);
         }
+        None => {
+            app_state.error_message = Some(String::from(if redo {
+                "Nothing to redo"
+            } else {
+                "Nothing to undo"
+            }));
+        }
+    }
+}
+
+fn handle_error(k: KeyEvent, app_state: &mut AppState) -> bool {
+    if matches!(k.code, event::KeyCode::Esc | event::KeyCode::Enter) {
+        app_state.error_message = None;
     }
+
     false
 }
 
-fn handle_move(k: KeyEvent, app_state: &mut AppState) -> bool {
+fn handle_load_error(k: KeyEvent, app_state: &mut AppState) -> bool {
     match k.code {
-        event::KeyCode::Esc | event::KeyCode::Enter => {
-            app_state.is_moving = false;
-            crate::fs::write(app_state);
+        event::KeyCode::Char('o') if app_state.read_only => {
+            app_state.error_message = Some(String::from(READ_ONLY_MESSAGE));
         }
-        event::KeyCode::Char(c) => match c {
-            'j' => {
-                if let Some(selected_idx) = app_state.list_state.selected()
-                    && selected_idx < app_state.items.len() - 1
-                {
-                    app_state.items.swap(selected_idx, selected_idx + 1);
-                    app_state.list_state.select_next();
-                }
+        event::KeyCode::Char('o') => match crate::fs::set_aside(&app_state.file_path) {
+            Ok(_) => {
+                app_state.load_error = None;
+                load_lists(app_state, Vec::new());
             }
-            'k' => {
-                if let Some(selected_idx) = app_state.list_state.selected()
-                    && selected_idx > 0
-                {
-                    app_state.items.swap(selected_idx, selected_idx - 1);
-                    app_state.list_state.select_previous();
-                }
+            Err(e) => {
+                app_state.error_message = Some(e.to_string());
             }
-            _ => {}
         },
+        event::KeyCode::Char('b') => {
+            open_backups(app_state);
+        }
+        event::KeyCode::Char('q') | event::KeyCode::Esc => {
+            return true;
+        }
         _ => {}
     }
 
     false
 }
 
-fn handle_key(k: KeyEvent, app_state: &mut AppState) -> bool {
+fn open_backups(app_state: &mut AppState) {
+    app_state.backups = crate::fs::list_backups(&app_state.file_path, app_state.config.backups);
+    app_state.backup_list_state = ListState::default();
+
+    if app_state.backups.is_empty() {
+        app_state.error_message = Some(String::from("No backups found"));
+    } else {
+        app_state.backup_list_state.select_first();
+        app_state.is_restoring = true;
+    }
+}
+
+fn handle_restore(k: KeyEvent, app_state: &mut AppState) -> bool {
     match k.code {
         event::KeyCode::Esc => {
-            return true;
+            app_state.is_restoring = false;
         }
         event::KeyCode::Enter => {
-            if app_state.list_state.selected().is_some() {
-                app_state.is_open = true;
+            if let Some(backup) = app_state
+                .backup_list_state
+                .selected()
+                .and_then(|idx| app_state.backups.get(idx))
+            {
+                match crate::fs::read_backup(&backup.path) {
+                    Ok((lists, file_meta)) => {
+                        app_state.is_restoring = false;
+                        load_lists(app_state, lists);
+                        app_state.file_meta = file_meta;
+                        // so the list being replaced is among the backups too
+                        app_state.last_backup = None;
+
+                        // a corrupted list is never overwritten without keeping a copy
+                        if app_state.load_error.is_some() {
+                            match crate::fs::set_aside(&app_state.file_path) {
+                                Ok(_) => app_state.load_error = None,
+                                Err(e) => {
+                                    app_state.error_message = Some(e.to_string());
+                                    return false;
+                                }
+                            }
+                        }
+
+                        mark_dirty(app_state);
+                    }
+                    Err(e) => {
+                        app_state.error_message = Some(e.to_string());
+                    }
+                }
             }
         }
-        event::KeyCode::Char(c) => match c {
-            'a' => {
-                app_state.is_add_new = true;
-            }
-            'd' => {
-                if app_state.list_state.selected().is_some() {
-                    app_state.is_deleting = true;
+        event::KeyCode::Char('j') => {
+            app_state.backup_list_state.select_next();
+        }
+        event::KeyCode::Char('k') => {
+            app_state.backup_list_state.select_previous();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+// replaces every list and opens the first one, creating a default list if there are none
+fn load_lists(app_state: &mut AppState, mut lists: Vec<TodoList>) {
+    if lists.is_empty() {
+        lists.push(TodoList {
+            name: String::from(crate::fs::DEFAULT_LIST_NAME),
+            ..Default::default()
+        });
+    }
+
+    app_state.items = std::mem::take(&mut lists[0].items);
+    app_state.lists = lists;
+    app_state.current_list = 0;
+    app_state.list_state = ListState::default();
+    reselect(app_state, None);
+}
+
+fn switch_list(app_state: &mut AppState, idx: usize) {
+    app_state.lists[app_state.current_list].items = std::mem::take(&mut app_state.items);
+    app_state.items = std::mem::take(&mut app_state.lists[idx].items);
+    app_state.current_list = idx;
+
+    // tags differ between lists, so a filter from the previous list would hide everything
+    app_state.tag_filter.clear();
+    app_state.list_state = ListState::default();
+    reselect(app_state, None);
+}
+
+fn handle_list_name(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Char(c) => {
+            app_state.list_name_input.push(c);
+        }
+        event::KeyCode::Backspace => {
+            app_state.list_name_input.pop();
+        }
+        event::KeyCode::Enter => {
+            let name = app_state.list_name_input.trim().to_string();
+            if list_name_error(app_state, &name).is_some() {
+                return false;
+            }
+
+            if app_state.is_creating_list {
+                app_state.lists.push(TodoList {
+                    name,
+                    ..Default::default()
+                });
+                switch_list(app_state, app_state.lists.len() - 1);
+            } else {
+                app_state.lists[app_state.current_list].name = name;
+            }
+
+            app_state.is_creating_list = false;
+            app_state.is_renaming_list = false;
+            app_state.list_name_input.clear();
+            mark_dirty(app_state);
+        }
+        event::KeyCode::Esc => {
+            app_state.is_creating_list = false;
+            app_state.is_renaming_list = false;
+            app_state.list_name_input.clear();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+// list names must be unique, ignoring case, so tabs can be told apart
+fn list_name_error(app_state: &AppState, name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        Some("Name is empty")
+    } else if app_state.lists.iter().enumerate().any(|(idx, list)| {
+        list.name.eq_ignore_ascii_case(name)
+            && !(app_state.is_renaming_list && idx == app_state.current_list)
+    }) {
+        Some("Name is already in use")
+    } else {
+        None
+    }
+}
+
+fn handle_delete_list(k: KeyEvent, app_state: &mut AppState) -> bool {
+    if let event::KeyCode::Char(c) = k.code {
+        match c {
+            'y' => {
+                app_state.lists.remove(app_state.current_list);
+                app_state.current_list = app_state.current_list.min(app_state.lists.len() - 1);
+                app_state.items =
+                    std::mem::take(&mut app_state.lists[app_state.current_list].items);
+                app_state.tag_filter.clear();
+                app_state.list_state = ListState::default();
+                reselect(app_state, None);
+
+                app_state.is_deleting_list = false;
+                mark_dirty(app_state);
+            }
+            'n' => {
+                app_state.is_deleting_list = false;
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+// every list other than the open one, in tab order
+fn other_lists(app_state: &AppState) -> Vec<usize> {
+    (0..app_state.lists.len())
+        .filter(|&idx| idx != app_state.current_list)
+        .collect()
+}
+
+fn handle_move_to_list(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Esc => {
+            app_state.is_moving_to_list = false;
+        }
+        event::KeyCode::Enter => {
+            if let Some(target) = app_state
+                .list_picker_state
+                .selected()
+                .and_then(|pos| other_lists(app_state).get(pos).copied())
+                && let Some(idx) = selected_index(app_state)
+            {
+                // the subtree goes along, becoming a top level item of the other list
+                let moved = crate::tree::take_subtree(&mut app_state.items, idx);
+                app_state.lists[target].items.extend(moved);
+
+                reselect(app_state, None);
+                mark_dirty(app_state);
+            }
+            app_state.is_moving_to_list = false;
+        }
+        event::KeyCode::Char('j') => {
+            app_state.list_picker_state.select_next();
+        }
+        event::KeyCode::Char('k') => {
+            app_state.list_picker_state.select_previous();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_help(k: KeyEvent, app_state: &mut AppState) -> bool {
+    if matches!(
+        k.code,
+        event::KeyCode::Esc | event::KeyCode::Enter | event::KeyCode::Char('?' | 'q')
+    ) {
+        app_state.is_viewing_help = false;
+    }
+
+    false
+}
+
+fn handle_archive(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Esc => {
+            app_state.is_viewing_archive = false;
+        }
+        event::KeyCode::Enter | event::KeyCode::Char('r') => {
+            if let Some(pos) = app_state.archive_list_state.selected() {
+                let archive = &mut app_state.lists[app_state.current_list].archive;
+                if pos < archive.len() {
+                    let idx = crate::archive::restore(&mut app_state.items, archive, pos);
+                    reselect(app_state, Some(idx));
+
+                    if app_state.lists[app_state.current_list].archive.is_empty() {
+                        app_state.is_viewing_archive = false;
+                    }
+                    mark_dirty(app_state);
+                }
+            }
+        }
+        event::KeyCode::Char('j') => {
+            app_state.archive_list_state.select_next();
+        }
+        event::KeyCode::Char('k') => {
+            app_state.archive_list_state.select_previous();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_trash(k: KeyEvent, app_state: &mut AppState) -> bool {
+    let trash_len = app_state.lists[app_state.current_list].trash.len();
+    let selected = app_state
+        .trash_list_state
+        .selected()
+        .filter(|&pos| pos < trash_len);
+
+    match k.code {
+        event::KeyCode::Esc => {
+            app_state.is_viewing_trash = false;
+        }
+        event::KeyCode::Enter | event::KeyCode::Char('r') => {
+            if let Some(pos) = selected {
+                let trash = &mut app_state.lists[app_state.current_list].trash;
+                let idx = crate::trash::restore(&mut app_state.items, trash, pos);
+                reselect(app_state, Some(idx));
+                mark_dirty(app_state);
+            }
+        }
+        event::KeyCode::Char('p') => {
+            if let Some(pos) = selected {
+                crate::trash::purge(&mut app_state.lists[app_state.current_list].trash, pos);
+                mark_dirty(app_state);
+            }
+        }
+        event::KeyCode::Char('P') => {
+            app_state.lists[app_state.current_list].trash.clear();
+            mark_dirty(app_state);
+        }
+        event::KeyCode::Char('j') => {
+            app_state.trash_list_state.select_next();
+        }
+        event::KeyCode::Char('k') => {
+            app_state.trash_list_state.select_previous();
+        }
+        _ => {}
+    }
+
+    if app_state.lists[app_state.current_list].trash.is_empty() {
+        app_state.is_viewing_trash = false;
+    }
+
+    false
+}
+
+fn handle_open(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Esc => {
+            app_state.is_open = false;
+        }
+        event::KeyCode::Char('j') | event::KeyCode::Down => {
+            app_state.item_scroll += 1;
+        }
+        event::KeyCode::Char('k') | event::KeyCode::Up => {
+            app_state.item_scroll = app_state.item_scroll.saturating_sub(1);
+        }
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_add_new(k: KeyEvent, app_state: &mut AppState) -> FormAction {
+    match k.code {
+        event::KeyCode::Char('o') if k.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            app_state.editor_request = Some(EditorRequest::Form);
+        }
+        event::KeyCode::Char(_)
+        | event::KeyCode::Backspace
+        | event::KeyCode::Left
+        | event::KeyCode::Right
+            if app_state.input_state.select_state == InputSelectState::Priority =>
+        {
+            handle_priority_input(k, app_state);
+        }
+        // plain Enter submits, so newlines need a modifier
+        event::KeyCode::Enter
+            if k.modifiers
+                .intersects(event::KeyModifiers::SHIFT | event::KeyModifiers::ALT)
+                && app_state.input_state.select_state == InputSelectState::Description =>
+        {
+            app_state.input_state.description_input.insert('\n');
+        }
+        event::KeyCode::Enter => {
+            if app_state.input_state.validate().is_err() {
+                return FormAction::None;
+            } else {
+                return FormAction::Submit;
+            }
+        }
+        event::KeyCode::Esc => {
+            return FormAction::Escape;
+        }
+        event::KeyCode::Tab => {
+            app_state.input_state.select_state = app_state.input_state.select_state.next();
+        }
+        _ => {
+            if let Some(input) = app_state.input_state.selected_input() {
+                input.handle_key(k);
+            }
+        }
+    }
+
+    FormAction::None
+}
+
+fn handle_edit(k: KeyEvent, app_state: &mut AppState) -> FormAction {
+    match k.code {
+        event::KeyCode::Char('o') if k.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            app_state.editor_request = Some(EditorRequest::Form);
+        }
+        event::KeyCode::Char(_)
+        | event::KeyCode::Backspace
+        | event::KeyCode::Left
+        | event::KeyCode::Right
+            if app_state.input_state.select_state == InputSelectState::Priority =>
+        {
+            handle_priority_input(k, app_state);
+        }
+        // plain Enter submits, so newlines need a modifier
+        event::KeyCode::Enter
+            if k.modifiers
+                .intersects(event::KeyModifiers::SHIFT | event::KeyModifiers::ALT)
+                && app_state.input_state.select_state == InputSelectState::Description =>
+        {
+            app_state.input_state.description_input.insert('\n');
+        }
+        event::KeyCode::Enter => {
+            if app_state.input_state.validate().is_err() {
+                return FormAction::None;
+            } else {
+                return FormAction::Submit;
+            }
+        }
+        event::KeyCode::Esc => {
+            return FormAction::Escape;
+        }
+        event::KeyCode::Tab => {
+            app_state.input_state.select_state = app_state.input_state.select_state.next();
+        }
+        _ => {
+            if let Some(input) = app_state.input_state.selected_input() {
+                input.handle_key(k);
+            }
+        }
+    }
+
+    FormAction::None
+}
+
+fn handle_priority_input(k: KeyEvent, app_state: &mut AppState) {
+    let priority = &mut app_state.input_state.priority;
+    match k.code {
+        event::KeyCode::Left | event::KeyCode::Char('h' | '-') => *priority = priority.lower(),
+        event::KeyCode::Right | event::KeyCode::Char('l' | '+') => *priority = priority.raise(),
+        event::KeyCode::Backspace => *priority = Priority::None,
+        event::KeyCode::Char(c) => {
+            if let Some(p) = Priority::from_digit(c) {
+                *priority = p;
+            }
+        }
+        _ => {}
+    }
+}
+
+// indices into app_state.items in the order they are shown
+fn visible_items(app_state: &AppState) -> Vec<usize> {
+    let mut view = Vec::new();
+    push_visible(
+        app_state,
+        crate::tree::roots(&app_state.items),
+        false,
+        &mut view,
+    );
+    view
+}
+
+// the same order, but including items hidden inside collapsed parents
+fn all_items_in_view_order(app_state: &AppState) -> Vec<usize> {
+    let mut view = Vec::new();
+    push_visible(
+        app_state,
+        crate::tree::roots(&app_state.items),
+        true,
+        &mut view,
+    );
+    view
+}
+
+fn push_visible(
+    app_state: &AppState,
+    mut siblings: Vec<usize>,
+    include_collapsed: bool,
+    view: &mut Vec<usize>,
+) {
+    let items = &app_state.items;
+
+    // the sorts are stable, so ties keep their manual order, and newest comes first
+    match app_state.sort_mode {
+        SortMode::Manual => {}
+        SortMode::Priority => siblings.sort_by_key(|&idx| std::cmp::Reverse(items[idx].priority)),
+        SortMode::Created => siblings.sort_by_key(|&idx| std::cmp::Reverse(items[idx].created_at)),
+        SortMode::Modified => {
+            siblings.sort_by_key(|&idx| std::cmp::Reverse(items[idx].modified_at))
+        }
+        SortMode::Completed => {
+            siblings.sort_by_key(|&idx| std::cmp::Reverse(items[idx].completed_at))
+        }
+    }
+
+    for idx in siblings {
+        // parents stay visible when one of their subtasks matches the filter or is still open
+        let is_match = (idx..crate::tree::subtree_end(items, idx)).any(|i| {
+            crate::tags::matches(&items[i], &app_state.tag_filter, app_state.tag_filter_all)
+                && !(app_state.hide_done && items[i].is_done)
+        });
+        if !is_match {
+            continue;
+        }
+
+        view.push(idx);
+        if include_collapsed || !items[idx].collapsed {
+            push_visible(
+                app_state,
+                crate::tree::children(items, idx),
+                include_collapsed,
+                view,
+            );
+        }
+    }
+}
+
+fn selected_index(app_state: &AppState) -> Option<usize> {
+    app_state
+        .list_state
+        .selected()
+        .and_then(|pos| visible_items(app_state).get(pos).copied())
+}
+
+fn select_index(app_state: &mut AppState, idx: usize) {
+    let pos = visible_items(app_state).iter().position(|&i| i == idx);
+    app_state.list_state.select(pos);
+}
+
+fn handle_tag_filter(k: KeyEvent, app_state: &mut AppState) -> bool {
+    let tags = crate::tags::counts(&app_state.items);
+
+    match k.code {
+        event::KeyCode::Esc | event::KeyCode::Enter => {
+            app_state.is_filtering_tags = false;
+        }
+        event::KeyCode::Char(c) => match c {
+            'j' => {
+                app_state.tag_list_state.select_next();
+            }
+            'k' => {
+                app_state.tag_list_state.select_previous();
+            }
+            ' ' => {
+                if let Some((tag, _)) = app_state
+                    .tag_list_state
+                    .selected()
+                    .and_then(|pos| tags.get(pos))
+                {
+                    let selected = selected_index(app_state);
+
+                    if let Some(pos) = app_state
+                        .tag_filter
+                        .iter()
+                        .position(|t| t.eq_ignore_ascii_case(tag))
+                    {
+                        app_state.tag_filter.remove(pos);
+                    } else {
+                        app_state.tag_filter.push(tag.clone());
+                    }
+
+                    reselect(app_state, selected);
                 }
             }
             'm' => {
-                if app_state.list_state.selected().is_some() {
+                let selected = selected_index(app_state);
+                app_state.tag_filter_all = !app_state.tag_filter_all;
+                reselect(app_state, selected);
+            }
+            'c' => {
+                let selected = selected_index(app_state);
+                app_state.tag_filter.clear();
+                reselect(app_state, selected);
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+
+    false
+}
+
+// expands every collapsed ancestor so the item shows up in the list
+fn reveal(app_state: &mut AppState, idx: usize) {
+    let mut current = idx;
+    let mut expanded = false;
+    while let Some(parent) = crate::tree::parent(&app_state.items, current) {
+        expanded |= app_state.items[parent].collapsed;
+        app_state.items[parent].collapsed = false;
+        current = parent;
+    }
+
+    if expanded {
+        fold_changed(app_state);
+    }
+}
+
+// folds are saved with the list, but in read-only mode they only change the view
+fn fold_changed(app_state: &mut AppState) {
+    if app_state.read_only {
+        app_state.saved_snapshot = crate::fs::snapshot(app_state);
+    } else {
+        mark_dirty(app_state);
+    }
+}
+
+fn handle_search(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Char(c) => {
+            app_state.search_query.push(c);
+            jump_to_match(app_state, app_state.search_origin, true, false);
+        }
+        event::KeyCode::Backspace => {
+            app_state.search_query.pop();
+            jump_to_match(app_state, app_state.search_origin, true, false);
+        }
+        event::KeyCode::Enter => {
+            // the query stays highlighted for n and N
+            app_state.is_searching = false;
+        }
+        event::KeyCode::Esc => {
+            app_state.is_searching = false;
+            app_state.search_query.clear();
+            reselect(app_state, app_state.search_origin);
+        }
+        _ => {}
+    }
+
+    false
+}
+
+// moves the cursor to the nearest match from `from`, wrapping around the list
+fn jump_to_match(app_state: &mut AppState, from: Option<usize>, forward: bool, skip_from: bool) {
+    let order = all_items_in_view_order(app_state);
+    if order.is_empty() || app_state.search_query.is_empty() {
+        return;
+    }
+
+    let len = order.len();
+    let start = from
+        .and_then(|idx| order.iter().position(|&i| i == idx))
+        .unwrap_or(0);
+
+    let found = (0..len)
+        .map(|step| (step + usize::from(skip_from)) % len)
+        .map(|step| {
+            if forward {
+                order[(start + step) % len]
+            } else {
+                order[(start + len - step) % len]
+            }
+        })
+        .find(|&idx| crate::search::matches(&app_state.items[idx], &app_state.search_query));
+
+    if let Some(idx) = found {
+        reveal(app_state, idx);
+        select_index(app_state, idx);
+    }
+}
+
+// (list, item) pairs matching the finder query, best match first
+fn finder_results(app_state: &AppState) -> Vec<(usize, usize)> {
+    let matcher = SkimMatcherV2::default();
+    let mut results = Vec::new();
+
+    for list in 0..app_state.lists.len() {
+        for (idx, item) in app_state.list_items(list).iter().enumerate() {
+            if app_state.finder_query.is_empty() {
+                results.push((0, list, idx));
+            } else if let Some(score) =
+                crate::search::fuzzy_score(&matcher, item, &app_state.finder_query)
+            {
+                results.push((score, list, idx));
+            }
+        }
+    }
+
+    // stable, so equal scores keep list order
+    results.sort_by_key(|&(score, ..)| std::cmp::Reverse(score));
+    results
+        .into_iter()
+        .map(|(_, list, idx)| (list, idx))
+        .collect()
+}
+
+fn handle_finder(k: KeyEvent, app_state: &mut AppState) -> bool {
+    let is_control = k.modifiers.contains(event::KeyModifiers::CONTROL);
+
+    match k.code {
+        event::KeyCode::Esc => {
+            app_state.is_finding = false;
+        }
+        event::KeyCode::Enter => {
+            if let Some((list, idx)) = app_state
+                .finder_list_state
+                .selected()
+                .and_then(|pos| finder_results(app_state).get(pos).copied())
+            {
+                if list != app_state.current_list {
+                    switch_list(app_state, list);
+                }
+                reveal(app_state, idx);
+                if !visible_items(app_state).contains(&idx) {
+                    // the tag filter or hiding done items hides it
+                    app_state.tag_filter.clear();
+                    app_state.hide_done = false;
+                }
+                select_index(app_state, idx);
+                app_state.is_finding = false;
+            }
+        }
+        event::KeyCode::Down => {
+            app_state.finder_list_state.select_next();
+        }
+        event::KeyCode::Char('n') if is_control => {
+            app_state.finder_list_state.select_next();
+        }
+        event::KeyCode::Up => {
+            app_state.finder_list_state.select_previous();
+        }
+        event::KeyCode::Char('p') if is_control => {
+            app_state.finder_list_state.select_previous();
+        }
+        event::KeyCode::Char(c) => {
+            app_state.finder_query.push(c);
+            app_state.finder_list_state.select_first();
+        }
+        event::KeyCode::Backspace => {
+            app_state.finder_query.pop();
+            app_state.finder_list_state.select_first();
+        }
+        _ => {}
+    }
+
+    false
+}
+
+// (position of the selected item among the matches, number of matches)
+fn search_position(app_state: &AppState) -> (Option<usize>, usize) {
+    let matches: Vec<usize> = all_items_in_view_order(app_state)
+        .into_iter()
+        .filter(|&idx| crate::search::matches(&app_state.items[idx], &app_state.search_query))
+        .collect();
+    let selected = selected_index(app_state);
+
+    (
+        matches.iter().position(|&idx| Some(idx) == selected),
+        matches.len(),
+    )
+}
+
+// keeps the cursor on the same item after the view changed, or moves it to the top
+fn reselect(app_state: &mut AppState, previous: Option<usize>) {
+    match previous {
+        Some(idx) if visible_items(app_state).contains(&idx) => select_index(app_state, idx),
+        _ if visible_items(app_state).is_empty() => app_state.list_state.select(None),
+        _ => app_state.list_state.select_first(),
+    }
+}
+
+fn handle_complete_subtasks(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Char(c @ ('y' | 'n')) => {
+            if let Some(idx) = selected_index(app_state) {
+                let end = if c == 'y' {
+                    crate::tree::subtree_end(&app_state.items, idx)
+                } else {
+                    idx + 1
+                };
+                app_state.items[idx..end]
+                    .iter_mut()
+                    .filter(|item| !item.is_done)
+                    .for_each(|item| item.set_done(true));
+                mark_dirty(app_state);
+            }
+            app_state.is_completing_subtasks = false;
+        }
+        event::KeyCode::Esc => {
+            app_state.is_completing_subtasks = false;
+        }
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_delete(k: KeyEvent, app_state: &mut AppState) -> bool {
+    if let event::KeyCode::Char(c) = k.code {
+        match c {
+            'y' => {
+                if let Some(idx) = selected_index(app_state) {
+                    crate::trash::delete(
+                        &mut app_state.items,
+                        &mut app_state.lists[app_state.current_list].trash,
+                        idx,
+                    );
+
+                    mark_dirty(app_state);
+                }
+                app_state.is_deleting = false;
+            }
+            'n' => {
+                app_state.is_deleting = false;
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+fn handle_move(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Esc | event::KeyCode::Enter => {
+            app_state.is_moving = false;
+            mark_dirty(app_state);
+        }
+        event::KeyCode::Char(c) => match c {
+            'j' | 'k' | 'h' | 'l' | '<' | '>' => {
+                if let Some(idx) = selected_index(app_state) {
+                    let items = &mut app_state.items;
+                    let moved = match c {
+                        'j' => crate::tree::move_down(items, idx),
+                        'k' => crate::tree::move_up(items, idx),
+                        'l' | '>' => crate::tree::indent(items, idx),
+                        _ => crate::tree::outdent(items, idx),
+                    };
+
+                    if let Some(new_idx) = moved {
+                        select_index(app_state, new_idx);
+                    }
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+
+    false
+}
+
+fn handle_key(k: KeyEvent, app_state: &mut AppState) -> bool {
+    match k.code {
+        event::KeyCode::Esc => {
+            return true;
+        }
+        event::KeyCode::Enter if selected_index(app_state).is_some() => {
+            app_state.is_open = true;
+            app_state.item_scroll = 0;
+        }
+        event::KeyCode::Char('r') if k.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            undo(app_state, true);
+        }
+        event::KeyCode::Tab => {
+            switch_list(
+                app_state,
+                (app_state.current_list + 1) % app_state.lists.len(),
+            );
+        }
+        event::KeyCode::BackTab => {
+            switch_list(
+                app_state,
+                (app_state.current_list + app_state.lists.len() - 1) % app_state.lists.len(),
+            );
+        }
+        event::KeyCode::Char(c) => match c {
+            'a' => {
+                app_state.is_add_new = true;
+            }
+            'A' => {
+                if let Some(idx) = selected_index(app_state) {
+                    app_state.add_parent = Some(idx);
+                    app_state.is_add_new = true;
+                }
+            }
+            'h' => {
+                if let Some(idx) = selected_index(app_state) {
+                    if crate::tree::has_children(&app_state.items, idx)
+                        && !app_state.items[idx].collapsed
+                    {
+                        app_state.items[idx].collapsed = true;
+                        fold_changed(app_state);
+                    } else if let Some(parent) = crate::tree::parent(&app_state.items, idx) {
+                        select_index(app_state, parent);
+                    }
+                }
+            }
+            'l' => {
+                if let Some(idx) = selected_index(app_state)
+                    && app_state.items[idx].collapsed
+                {
+                    app_state.items[idx].collapsed = false;
+                    fold_changed(app_state);
+                }
+            }
+            'd' if selected_index(app_state).is_some() => {
+                app_state.is_deleting = true;
+            }
+            'm' => {
+                if let Some(idx) = selected_index(app_state) {
+                    // moving works on the manual order, so leave the sorted view first
+                    app_state.sort_mode = SortMode::Manual;
+                    select_index(app_state, idx);
                     app_state.is_moving = true;
                 }
             }
+            't' => {
+                app_state.is_filtering_tags = true;
+                app_state.tag_list_state.select_first();
+            }
+            's' => {
+                let selected = selected_index(app_state);
+                app_state.sort_mode = app_state.sort_mode.next();
+                if let Some(idx) = selected {
+                    select_index(app_state, idx);
+                }
+            }
+            '+' | '-' => {
+                if let Some(idx) = selected_index(app_state) {
+                    let item = &mut app_state.items[idx];
+                    item.priority = if c == '+' {
+                        item.priority.raise()
+                    } else {
+                        item.priority.lower()
+                    };
+                    item.modified_at = Some(now());
+                    // keep the cursor on the item if the sorted view reorders it
+                    select_index(app_state, idx);
+                    mark_dirty(app_state);
+                }
+            }
+            'b' => {
+                open_backups(app_state);
+            }
+            'L' => {
+                app_state.is_creating_list = true;
+            }
+            'u' => {
+                undo(app_state, false);
+            }
+            'H' => {
+                let selected = selected_index(app_state);
+                app_state.hide_done = !app_state.hide_done;
+                reselect(app_state, selected);
+            }
+            'C' => {
+                let archived = crate::archive::archive_done(
+                    &mut app_state.items,
+                    &mut app_state.lists[app_state.current_list].archive,
+                    |_| true,
+                );
+
+                if archived > 0 {
+                    // indices shifted, so fall back to the top of the list
+                    reselect(app_state, None);
+                    mark_dirty(app_state);
+                }
+            }
+            'T' => {
+                if app_state.lists[app_state.current_list].trash.is_empty() {
+                    app_state.error_message = Some(String::from("The trash is empty"));
+                } else {
+                    app_state.trash_list_state = ListState::default();
+                    app_state.trash_list_state.select_first();
+                    app_state.is_viewing_trash = true;
+                }
+            }
+            'V' => {
+                if app_state.lists[app_state.current_list].archive.is_empty() {
+                    app_state.error_message = Some(String::from("The archive is empty"));
+                } else {
+                    app_state.archive_list_state = ListState::default();
+                    app_state.archive_list_state.select_first();
+                    app_state.is_viewing_archive = true;
+                }
+            }
+            '?' => {
+                app_state.is_viewing_help = true;
+            }
+            '/' => {
+                app_state.search_origin = selected_index(app_state);
+                app_state.search_query.clear();
+                app_state.is_searching = true;
+            }
+            'f' => {
+                app_state.finder_query.clear();
+                app_state.finder_list_state = ListState::default();
+                app_state.finder_list_state.select_first();
+                app_state.is_finding = true;
+            }
+            'n' | 'N' if !app_state.search_query.is_empty() => {
+                jump_to_match(app_state, selected_index(app_state), c == 'n', true);
+            }
+            'R' => {
+                app_state.list_name_input = app_state.lists[app_state.current_list].name.clone();
+                app_state.is_renaming_list = true;
+            }
+            'X' => {
+                if app_state.lists.len() > 1 {
+                    app_state.is_deleting_list = true;
+                } else {
+                    app_state.error_message = Some(String::from("The last list cannot be deleted"));
+                }
+            }
+            'M' if selected_index(app_state).is_some() => {
+                if app_state.lists.len() > 1 {
+                    app_state.list_picker_state = ListState::default();
+                    app_state.list_picker_state.select_first();
+                    app_state.is_moving_to_list = true;
+                } else {
+                    app_state.error_message = Some(String::from(
+                        "Create another list with [L] to move items to",
+                    ));
+                }
+            }
             'e' => {
-                if let Some(item) = app_state
-                    .list_state
-                    .selected()
-                    .and_then(|idx| app_state.items.get(idx))
-                {
+                if let Some(idx) = selected_index(app_state) {
                     app_state.is_editing = true;
-                    app_state.input_state.name_input = item.name.clone();
-                    app_state.input_state.description_input = item.description.clone();
+                    app_state.input_state = InputState::from_item(&app_state.items[idx]);
+                }
+            }
+            'E' => {
+                if let Some(idx) = selected_index(app_state) {
+                    app_state.editor_request = Some(EditorRequest::Item(idx));
                 }
             }
             'c' => {
-                if let Some(item) = app_state
-                    .list_state
-                    .selected()
-                    .and_then(|idx| app_state.items.get_mut(idx))
-                {
-                    item.is_done = !item.is_done;
+                if let Some(idx) = selected_index(app_state) {
+                    let item = &mut app_state.items[idx];
+                    if !item.is_done
+                        && let Some(recurrence) = item.recurrence.clone()
+                    {
+                        complete_recurring(item, &recurrence);
+                    } else if !item.is_done
+                        && crate::tree::open_descendants(&app_state.items, idx) > 0
+                    {
+                        app_state.is_completing_subtasks = true;
+                    } else {
+                        let item = &mut app_state.items[idx];
+                        item.set_done(!item.is_done);
+                    }
+                    mark_dirty(app_state);
                 }
             }
             'j' => {
@@ -331,24 +2090,42 @@ fn handle_key(k: KeyEvent, app_state: &mut AppState) -> bool {
     false
 }
 
+// recurring items are logged and rescheduled instead of being marked done
+fn complete_recurring(item: &mut TodoItem, recurrence: &Recurrence) {
+    let now = now();
+
+    item.completions.push(now);
+    item.modified_at = Some(now);
+    item.due_date = Some(recurrence.next(item.due_date, now.date()));
+}
+
 fn render(frame: &mut Frame, app_state: &mut AppState) {
     let [border_area] = Layout::vertical([Constraint::Fill(1)])
         .margin(1)
         .areas(frame.area());
 
-    let title_bottom = if app_state.is_moving {
+    let title_bottom = if app_state.is_searching {
+        " /".to_span().fg(Color::Yellow)
+            + app_state.search_query.as_str().fg(Color::default())
+            + "|".to_span().fg(Color::default())
+            + " Done ".to_span().fg(Color::Yellow)
+            + "[Enter]".to_span().fg(Color::Green)
+            + " Cancel ".to_span().fg(Color::Yellow)
+            + "[Esc] ".to_span().fg(Color::Green)
+    } else if app_state.is_moving {
         " Move Up ".to_span().fg(Color::Yellow)
             + "[k]".to_span().fg(Color::Green)
             + " Move Down ".to_span().fg(Color::Yellow)
             + "[j]".to_span().fg(Color::Green)
+            + " Outdent ".to_span().fg(Color::Yellow)
+            + "[h]".to_span().fg(Color::Green)
+            + " Indent ".to_span().fg(Color::Yellow)
+            + "[l]".to_span().fg(Color::Green)
             + " Stop Moving ".to_span().fg(Color::Yellow)
             + "[Enter/Esc] ".to_span().fg(Color::Green)
     } else {
-        " Up ".to_span().fg(Color::Yellow)
-            + "[k]".to_span().fg(Color::Green)
-            + " Down ".to_span().fg(Color::Yellow)
-            + "[j]".to_span().fg(Color::Green)
-            + " New ".to_span().fg(Color::Yellow)
+        // the rest is listed by the help popup, as all of it would not fit
+        " New ".to_span().fg(Color::Yellow)
             + "[a]".to_span().fg(Color::Green)
             + " Edit ".to_span().fg(Color::Yellow)
             + "[e]".to_span().fg(Color::Green)
@@ -356,69 +2133,856 @@ fn render(frame: &mut Frame, app_state: &mut AppState) {
             + "[d]".to_span().fg(Color::Green)
             + " Complete ".to_span().fg(Color::Yellow)
             + "[c]".to_span().fg(Color::Green)
-            + " Move ".to_span().fg(Color::Yellow)
-            + "[m]".to_span().fg(Color::Green)
+            + " Help ".to_span().fg(Color::Yellow)
+            + "[?]".to_span().fg(Color::Green)
             + " Exit ".to_span().fg(Color::Yellow)
             + "[Esc] ".to_span().fg(Color::Green)
     };
 
-    Block::bordered()
-        .border_type(ratatui::widgets::BorderType::Rounded)
-        .title(
-            if app_state.is_moving {
-                " Moving "
-            } else {
-                " TUIDoList "
-            }
-            .to_span()
-            .into_centered_line()
-            .fg(Color::Yellow),
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .title(
+            if app_state.is_moving {
+                " Moving "
+            } else {
+                " TUIDoList "
+            }
+            .to_span()
+            .into_centered_line()
+            .fg(Color::Yellow),
+        )
+        .title(if app_state.read_only {
+            Line::default()
+        } else {
+            match app_state.save_state {
+                SaveState::Saved => " Saved ".fg(Color::DarkGray),
+                SaveState::Unsaved => " Unsaved ".fg(Color::Yellow),
+                SaveState::Saving => " Saving... ".fg(Color::Yellow),
+                SaveState::Failed => " Save Failed ".fg(Color::Red),
+            }
+            .into_left_aligned_line()
+        })
+        .title(if app_state.tag_filter.is_empty() {
+            Line::default()
+        } else {
+            let separator = if app_state.tag_filter_all {
+                " & "
+            } else {
+                " | "
+            };
+            (" ".to_span()
+                + app_state
+                    .tag_filter
+                    .iter()
+                    .map(|tag| format!("#{tag}"))
+                    .collect::<Vec<_>>()
+                    .join(separator)
+                    .fg(Color::Yellow)
+                + " ".to_span())
+            .left_aligned()
+        })
+        .title(if app_state.read_only && !app_state.is_locked {
+            " Read Only ".fg(Color::Red).into_right_aligned_line()
+        } else {
+            Line::default()
+        })
+        .title(if app_state.hide_done {
+            " Done Hidden ".fg(Color::Yellow).into_right_aligned_line()
+        } else {
+            Line::default()
+        })
+        .title(if app_state.sort_mode == SortMode::Manual {
+            Line::default()
+        } else {
+            format!(" Sorted By {} ", app_state.sort_mode.label())
+                .fg(Color::Yellow)
+                .into_right_aligned_line()
+        })
+        .title_bottom(title_bottom.alignment(ratatui::layout::HorizontalAlignment::Center))
+        .title_bottom(if app_state.search_query.is_empty() {
+            Line::default()
+        } else {
+            let (position, count) = search_position(app_state);
+            let position = position.map_or(String::from("-"), |pos| (pos + 1).to_string());
+            format!(" {position}/{count} ")
+                .fg(if count == 0 {
+                    Color::Red
+                } else {
+                    Color::Yellow
+                })
+                .into_right_aligned_line()
+        })
+        .fg(if app_state.is_moving {
+            Color::Green
+        } else {
+            Color::Cyan
+        })
+        .render(border_area, frame.buffer_mut());
+    render_tabs(frame, app_state);
+    render_list(frame, app_state);
+
+    if app_state.is_add_new {
+        render_add(frame, app_state);
+    }
+
+    if app_state.is_editing {
+        render_edit(frame, app_state);
+    }
+
+    if app_state.is_deleting {
+        render_delete(frame, app_state);
+    }
+
+    if app_state.is_completing_subtasks
+        && let Some(idx) = selected_index(app_state)
+    {
+        let open = crate::tree::open_descendants(&app_state.items, idx);
+        render_message(
+            frame,
+            " Complete ",
+            vec![
+                ("Also complete ".to_span().fg(Color::Yellow)
+                    + format!("{open} open subtask{}", if open == 1 { "" } else { "s" })
+                        .fg(Color::Green)
+                    + " of ".to_span().fg(Color::Yellow)
+                    + app_state.items[idx].name.as_str().fg(Color::Green)
+                    + "?".to_span().fg(Color::Yellow))
+                .centered(),
+            ],
+            " Yes ".to_span().fg(Color::Yellow)
+                + "[y]".to_span().fg(Color::Green)
+                + " Only This ".to_span().fg(Color::Yellow)
+                + "[n]".to_span().fg(Color::Green)
+                + " Cancel ".to_span().fg(Color::Yellow)
+                + "[Esc] ".to_span().fg(Color::Green),
+        );
+    }
+
+    if app_state.is_open {
+        render_item(frame, app_state);
+    }
+
+    if app_state.is_creating_list || app_state.is_renaming_list {
+        let name = app_state.list_name_input.trim();
+        let title = if app_state.is_creating_list {
+            " New List "
+        } else {
+            " Rename List "
+        };
+
+        render_message(
+            frame,
+            title,
+            vec![
+                "Name: ".to_span().fg(Color::Yellow)
+                    + app_state.list_name_input.as_str().fg(Color::default())
+                    + "|".to_span().fg(Color::default()),
+                match list_name_error(app_state, name) {
+                    Some(e) if !name.is_empty() => e.fg(Color::Red).into(),
+                    _ => Line::default(),
+                },
+            ],
+            " Submit ".to_span().fg(Color::Yellow)
+                + "[Enter]".to_span().fg(Color::Green)
+                + " Cancel ".to_span().fg(Color::Yellow)
+                + "[Esc] ".to_span().fg(Color::Green),
+        );
+    }
+
+    if app_state.is_deleting_list {
+        let count = app_state.items.len();
+        render_message(
+            frame,
+            " Delete List ",
+            vec![
+                ("Delete list ".to_span().fg(Color::Yellow)
+                    + app_state.lists[app_state.current_list]
+                        .name
+                        .as_str()
+                        .fg(Color::Green)
+                    + format!(
+                        " and its {count} item{}?",
+                        if count == 1 { "" } else { "s" }
+                    )
+                    .fg(Color::Yellow))
+                .centered(),
+            ],
+            " Yes ".to_span().fg(Color::Yellow)
+                + "[y]".to_span().fg(Color::Green)
+                + " No ".to_span().fg(Color::Yellow)
+                + "[n] ".to_span().fg(Color::Green),
+        );
+    }
+
+    if app_state.is_moving_to_list {
+        render_list_picker(frame, app_state);
+    }
+
+    if app_state.is_finding {
+        render_finder(frame, app_state);
+    }
+
+    if app_state.is_viewing_archive {
+        render_archive(frame, app_state);
+    }
+
+    if app_state.is_viewing_trash {
+        render_trash(frame, app_state);
+    }
+
+    if let Some(message) = &app_state.load_error {
+        let aside_name = app_state
+            .file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            + ".corrupt";
+
+        render_message(
+            frame,
+            " Corrupted List ",
+            vec![
+                message.to_span().fg(Color::default()).into(),
+                Line::default(),
+                ("Open anyway with an empty list? The current file will be kept as "
+                    .to_span()
+                    .fg(Color::Yellow)
+                    + aside_name.to_span().fg(Color::Green)),
+            ],
+            " Open Anyway ".to_span().fg(Color::Yellow)
+                + "[o]".to_span().fg(Color::Green)
+                + " Restore Backup ".to_span().fg(Color::Yellow)
+                + "[b]".to_span().fg(Color::Green)
+                + " Quit ".to_span().fg(Color::Yellow)
+                + "[q] ".to_span().fg(Color::Green),
+        );
+    }
+
+    if app_state.is_locked {
+        render_message(
+            frame,
+            " List In Use ",
+            vec![
+                ("Another instance has ".to_span().fg(Color::Yellow)
+                    + app_state.file_path.to_string_lossy().fg(Color::Green)
+                    + " open.".to_span().fg(Color::Yellow)),
+                Line::default(),
+                "Open it read-only, or take over and save from here as well. A save never overwrites changes it has not seen."
+                    .fg(Color::Yellow)
+                    .into(),
+            ],
+            " Read Only ".to_span().fg(Color::Yellow)
+                + "[r]".to_span().fg(Color::Green)
+                + " Take Over ".to_span().fg(Color::Yellow)
+                + "[t]".to_span().fg(Color::Green)
+                + " Quit ".to_span().fg(Color::Yellow)
+                + "[q] ".to_span().fg(Color::Green),
+        );
+    }
+
+    if app_state.is_filtering_tags {
+        render_tag_filter(frame, app_state);
+    }
+
+    if app_state.is_restoring {
+        render_restore(frame, app_state);
+    }
+
+    if app_state.conflict.is_some() {
+        render_conflict(frame, app_state);
+    }
+
+    if app_state.is_viewing_help {
+        render_help(frame);
+    }
+
+    if let Some(message) = &app_state.error_message {
+        render_message(
+            frame,
+            " Error ",
+            message
+                .lines()
+                .map(|line| line.fg(Color::Red).into())
+                .collect(),
+            " Dismiss ".to_span().fg(Color::Yellow) + "[Esc/Enter] ".to_span().fg(Color::Green),
+        );
+    }
+}
+
+fn render_help(frame: &mut Frame) {
+    let area = frame.area();
+    let rows = HELP.len().div_ceil(2);
+    let popup_width = 76.min(area.width);
+    let popup_height = (rows as u16 + 2).min(area.height);
+    let popup_x = (area.width - popup_width) / 2;
+    let popup_y = (area.height - popup_height) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    let block = Block::bordered()
+        .fg(Color::Cyan)
+        .padding(Padding::horizontal(1))
+        .title(" Keys ".to_span().fg(Color::Yellow).into_centered_line())
+        .title_bottom(
+            (" Close ".to_span().fg(Color::Yellow) + "[Esc/?] ".to_span().fg(Color::Green))
+                .alignment(ratatui::layout::HorizontalAlignment::Center),
+        )
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(popup_area);
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    block.render(popup_area, frame.buffer_mut());
+
+    let columns: [ratatui::layout::Rect; 2] =
+        Layout::horizontal([Constraint::Fill(1); 2]).areas(inner);
+    for (column, keys) in columns.into_iter().zip(HELP.chunks(rows)) {
+        Paragraph::new(
+            keys.iter()
+                .map(|(key, action)| {
+                    format!("{key:<10}").fg(Color::Green) + action.fg(Color::Yellow)
+                })
+                .collect::<Vec<Line>>(),
+        )
+        .render(column, frame.buffer_mut());
+    }
+}
+
+fn render_tag_filter(frame: &mut Frame, app_state: &mut AppState) {
+    let tags = crate::tags::counts(&app_state.items);
+
+    let area = frame.area();
+    let popup_width = (area.width / 3).max(52).min(area.width);
+    let popup_height = (tags.len().max(1) as u16 + 2).min(area.height);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    let block = Block::bordered()
+        .fg(Color::Cyan)
+        .padding(Padding::horizontal(1))
+        .title(
+            " Filter By Tag "
+                .to_span()
+                .fg(Color::Yellow)
+                .into_centered_line(),
+        )
+        .title(
+            if app_state.tag_filter_all {
+                " all "
+            } else {
+                " any "
+            }
+            .fg(Color::Yellow)
+            .into_right_aligned_line(),
+        )
+        .title_bottom(
+            (" Toggle ".to_span().fg(Color::Yellow)
+                + "[Space]".to_span().fg(Color::Green)
+                + " All/Any ".to_span().fg(Color::Yellow)
+                + "[m]".to_span().fg(Color::Green)
+                + " Clear ".to_span().fg(Color::Yellow)
+                + "[c]".to_span().fg(Color::Green)
+                + " Close ".to_span().fg(Color::Yellow)
+                + "[Esc] ".to_span().fg(Color::Green))
+            .alignment(ratatui::layout::HorizontalAlignment::Center),
+        )
+        .border_type(BorderType::Rounded);
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+
+    if tags.is_empty() {
+        Paragraph::new("no tags in use".to_span().fg(Color::default()))
+            .alignment(ratatui::layout::HorizontalAlignment::Center)
+            .block(block)
+            .render(popup_area, frame.buffer_mut());
+        return;
+    }
+
+    let list = List::new(tags.iter().map(|(tag, count)| {
+        let is_active = app_state
+            .tag_filter
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(tag));
+
+        ListItem::from(
+            if is_active { "[x] " } else { "[ ] " }.fg(Color::default())
+                + format!("#{tag}").fg(crate::tags::color(tag))
+                + format!(" ({count})").fg(Color::DarkGray),
+        )
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green))
+    .block(block);
+
+    frame.render_stateful_widget(list, popup_area, &mut app_state.tag_list_state);
+}
+
+fn render_conflict(frame: &mut Frame, app_state: &AppState) {
+    let Some((lists, _)) = &app_state.conflict else {
+        return;
+    };
+    let list_name = &app_state.lists[app_state.current_list].name;
+    // compare the open list with its counterpart on disk
+    let theirs = lists
+        .iter()
+        .find(|list| &list.name == list_name)
+        .or(lists.first())
+        .map_or(&[][..], |list| &list.items[..]);
+
+    let area = frame.area();
+    let popup_width = (area.width as f32 * 0.7) as u16;
+    let popup_height = (area.height as f32 * 0.7) as u16;
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    let block = Block::bordered()
+        .fg(Color::Cyan)
+        .padding(Padding::horizontal(1))
+        .title(
+            " Conflict "
+                .to_span()
+                .fg(Color::Yellow)
+                .into_centered_line(),
+        )
+        .title_bottom(
+            (" Keep Mine ".to_span().fg(Color::Yellow)
+                + "[m]".to_span().fg(Color::Green)
+                + " Take Theirs ".to_span().fg(Color::Yellow)
+                + "[t] ".to_span().fg(Color::Green))
+            .alignment(ratatui::layout::HorizontalAlignment::Center),
+        )
+        .border_type(BorderType::Rounded);
+
+    let [message_area, versions_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Fill(1)])
+            .areas(block.inner(popup_area));
+    let [mine_area, theirs_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(versions_area);
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    Paragraph::new(
+        "The list file was changed by another program while you had unsaved changes."
+            .fg(Color::Yellow),
+    )
+    .wrap(Wrap { trim: false })
+    .render(message_area, frame.buffer_mut());
+
+    for (title, items, area) in [
+        (" Mine ", &app_state.items[..], mine_area),
+        (" On Disk ", theirs, theirs_area),
+    ] {
+        Paragraph::new(
+            items
+                .iter()
+                .map(|item| {
+                    Line::from(format!(
+                        "{}{} {}",
+                        "  ".repeat(item.depth),
+                        if item.is_done { "[x]" } else { "[ ]" },
+                        item.name
+                    ))
+                    .fg(Color::default())
+                })
+                .collect::<Vec<_>>(),
+        )
+        .block(
+            Block::bordered()
+                .title(title.fg(Color::Yellow))
+                .fg(Color::Green)
+                .border_type(BorderType::Rounded),
+        )
+        .render(area, frame.buffer_mut());
+    }
+}
+
+fn render_tabs(frame: &mut Frame, app_state: &AppState) {
+    // the blank row between the border and the list
+    let [tabs_area] = Layout::vertical([Constraint::Fill(1)])
+        .horizontal_margin(3)
+        .vertical_margin(2)
+        .areas(frame.area());
+    let tabs_area = ratatui::layout::Rect {
+        height: 1.min(tabs_area.height),
+        ..tabs_area
+    };
+
+    Tabs::new(
+        app_state
+            .lists
+            .iter()
+            .map(|list| list.name.as_str().fg(Color::default())),
+    )
+    .select(app_state.current_list)
+    .highlight_style(Style::default().fg(Color::Green).bold())
+    .divider("|".fg(Color::Cyan))
+    .render(tabs_area, frame.buffer_mut());
+}
+
+fn render_finder(frame: &mut Frame, app_state: &mut AppState) {
+    let results = finder_results(app_state);
+    let matcher = SkimMatcherV2::default();
+    let total: usize = (0..app_state.lists.len())
+        .map(|list| app_state.list_items(list).len())
+        .sum();
+
+    let area = frame.area();
+    let popup_width = (area.width / 2).max(50).min(area.width);
+    let popup_height = (area.height as f32 * 0.6) as u16;
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    let block = Block::bordered()
+        .fg(Color::Cyan)
+        .padding(Padding::horizontal(1))
+        .title(" Find ".to_span().fg(Color::Yellow).into_centered_line())
+        .title(
+            format!(" {}/{total} ", results.len())
+                .fg(Color::Yellow)
+                .into_right_aligned_line(),
+        )
+        .title_bottom(
+            (" Up ".to_span().fg(Color::Yellow)
+                + "[↑/C-p]".to_span().fg(Color::Green)
+                + " Down ".to_span().fg(Color::Yellow)
+                + "[↓/C-n]".to_span().fg(Color::Green)
+                + " Go To ".to_span().fg(Color::Yellow)
+                + "[Enter]".to_span().fg(Color::Green)
+                + " Close ".to_span().fg(Color::Yellow)
+                + "[Esc] ".to_span().fg(Color::Green))
+            .alignment(ratatui::layout::HorizontalAlignment::Center),
+        )
+        .border_type(BorderType::Rounded);
+
+    let [query_area, results_area] = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)])
+        .areas(block.inner(popup_area));
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    Paragraph::new(
+        "> ".to_span().fg(Color::Yellow)
+            + app_state.finder_query.as_str().fg(Color::default())
+            + "|".to_span().fg(Color::default()),
+    )
+    .render(query_area, frame.buffer_mut());
+
+    let list = List::new(results.iter().map(|&(list, idx)| {
+        let item = &app_state.list_items(list)[idx];
+        let matched = crate::search::fuzzy_indices(&matcher, &item.name, &app_state.finder_query);
+
+        let mut line = Line::default();
+        for (i, c) in item.name.chars().enumerate() {
+            line.push_span(if matched.contains(&i) {
+                c.to_string().fg(Color::Yellow).bold()
+            } else if item.is_done {
+                c.to_string().fg(Color::DarkGray)
+            } else {
+                c.to_string().fg(Color::default())
+            });
+        }
+        line.extend(tag_chips(&item.tags));
+        if app_state.lists.len() > 1 {
+            line.push_span(format!("  {}", app_state.lists[list].name).fg(Color::DarkGray));
+        }
+
+        ListItem::from(line)
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green));
+
+    frame.render_stateful_widget(list, results_area, &mut app_state.finder_list_state);
+}
+
+fn render_archive(frame: &mut Frame, app_state: &mut AppState) {
+    let archive = &app_state.lists[app_state.current_list].archive;
+
+    let area = frame.area();
+    let popup_width = (area.width / 2).max(50).min(area.width);
+    let popup_height = (archive.len() as u16 + 2).min((area.height as f32 * 0.6) as u16);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+    let line_width = (popup_width as usize).saturating_sub(6);
+
+    let list = List::new(archive.iter().map(|item| {
+        let mut line = Line::from("  ".repeat(item.depth));
+        line.push_span(item.name.as_str().fg(Color::default()));
+
+        if let Some(completed_at) = item.completed_at {
+            let date = completed_at.format("%Y-%m-%d").to_string();
+            let gap = line_width.saturating_sub(line.width() + date.len()).max(1);
+
+            line.push_span(" ".repeat(gap));
+            line.push_span(date.fg(Color::DarkGray));
+        }
+
+        ListItem::from(line)
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green));
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_stateful_widget(
+        list.block(
+            Block::bordered()
+                .fg(Color::Cyan)
+                .padding(Padding::horizontal(1))
+                .title(" Archive ".to_span().fg(Color::Yellow).into_centered_line())
+                .title_bottom(
+                    (" Up ".to_span().fg(Color::Yellow)
+                        + "[k]".to_span().fg(Color::Green)
+                        + " Down ".to_span().fg(Color::Yellow)
+                        + "[j]".to_span().fg(Color::Green)
+                        + " Restore ".to_span().fg(Color::Yellow)
+                        + "[Enter/r]".to_span().fg(Color::Green)
+                        + " Close ".to_span().fg(Color::Yellow)
+                        + "[Esc] ".to_span().fg(Color::Green))
+                    .alignment(ratatui::layout::HorizontalAlignment::Center),
+                )
+                .border_type(BorderType::Rounded),
+        ),
+        popup_area,
+        &mut app_state.archive_list_state,
+    );
+}
+
+fn render_trash(frame: &mut Frame, app_state: &mut AppState) {
+    let trash = &app_state.lists[app_state.current_list].trash;
+
+    let area = frame.area();
+    let popup_width = (area.width / 2).max(72).min(area.width);
+    let popup_height = (trash.len() as u16 + 2).min((area.height as f32 * 0.6) as u16);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+    let line_width = (popup_width as usize).saturating_sub(6);
+
+    let list = List::new(trash.iter().map(|item| {
+        let mut line = Line::from("  ".repeat(item.depth));
+        line.push_span(item.name.as_str().fg(Color::default()));
+
+        if let Some(deleted_at) = item.deleted_at {
+            let date = format!("deleted {}", deleted_at.format("%Y-%m-%d %H:%M"));
+            let gap = line_width.saturating_sub(line.width() + date.len()).max(1);
+
+            line.push_span(" ".repeat(gap));
+            line.push_span(date.fg(Color::DarkGray));
+        }
+
+        ListItem::from(line)
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green));
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_stateful_widget(
+        list.block(
+            Block::bordered()
+                .fg(Color::Cyan)
+                .padding(Padding::horizontal(1))
+                .title(" Trash ".to_span().fg(Color::Yellow).into_centered_line())
+                .title_bottom(
+                    (" Select ".to_span().fg(Color::Yellow)
+                        + "[j/k]".to_span().fg(Color::Green)
+                        + " Restore ".to_span().fg(Color::Yellow)
+                        + "[Enter/r]".to_span().fg(Color::Green)
+                        + " Purge ".to_span().fg(Color::Yellow)
+                        + "[p]".to_span().fg(Color::Green)
+                        + " Purge All ".to_span().fg(Color::Yellow)
+                        + "[P]".to_span().fg(Color::Green)
+                        + " Close ".to_span().fg(Color::Yellow)
+                        + "[Esc] ".to_span().fg(Color::Green))
+                    .alignment(ratatui::layout::HorizontalAlignment::Center),
+                )
+                .border_type(BorderType::Rounded),
+        ),
+        popup_area,
+        &mut app_state.trash_list_state,
+    );
+}
+
+fn render_list_picker(frame: &mut Frame, app_state: &mut AppState) {
+    let targets = other_lists(app_state);
+
+    let area = frame.area();
+    let popup_width = (area.width / 3).max(44).min(area.width);
+    let popup_height = (targets.len() as u16 + 2).min(area.height);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    let list = List::new(targets.iter().map(|&idx| {
+        let list = &app_state.lists[idx];
+        ListItem::from(
+            list.name.as_str().fg(Color::default())
+                + format!(" ({})", list.items.len()).fg(Color::DarkGray),
         )
-        .title_bottom(title_bottom.alignment(ratatui::layout::HorizontalAlignment::Center))
-        .fg(if app_state.is_moving {
-            Color::Green
-        } else {
-            Color::Cyan
-        })
-        .render(border_area, frame.buffer_mut());
-    render_list(frame, app_state);
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green));
 
-    if app_state.is_add_new {
-        render_add(frame, app_state);
-    }
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_stateful_widget(
+        list.block(
+            Block::bordered()
+                .fg(Color::Cyan)
+                .padding(Padding::horizontal(1))
+                .title(
+                    " Move To List "
+                        .to_span()
+                        .fg(Color::Yellow)
+                        .into_centered_line(),
+                )
+                .title_bottom(
+                    (" Up ".to_span().fg(Color::Yellow)
+                        + "[k]".to_span().fg(Color::Green)
+                        + " Down ".to_span().fg(Color::Yellow)
+                        + "[j]".to_span().fg(Color::Green)
+                        + " Move ".to_span().fg(Color::Yellow)
+                        + "[Enter]".to_span().fg(Color::Green)
+                        + " Cancel ".to_span().fg(Color::Yellow)
+                        + "[Esc] ".to_span().fg(Color::Green))
+                    .alignment(ratatui::layout::HorizontalAlignment::Center),
+                )
+                .border_type(BorderType::Rounded),
+        ),
+        popup_area,
+        &mut app_state.list_picker_state,
+    );
+}
 
-    if app_state.is_editing {
-        render_edit(frame, app_state);
-    }
+fn render_restore(frame: &mut Frame, app_state: &mut AppState) {
+    let area = frame.area();
+    let popup_width = (area.width / 2).max(30).min(area.width);
+    let popup_height = (app_state.backups.len() as u16 + 2).min(area.height);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
 
-    if app_state.is_deleting {
-        render_delete(frame, app_state);
-    }
+    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
 
-    if app_state.is_open {
-        render_item(frame, app_state);
+    let now = std::time::SystemTime::now();
+    let list = List::new(app_state.backups.iter().map(|backup| {
+        let name = backup
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let age = backup
+            .modified
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map(format_age)
+            .unwrap_or(String::from("unknown age"));
+        let count = match backup.item_count {
+            Some(1) => String::from("1 item"),
+            Some(n) => format!("{n} items"),
+            None => String::from("unreadable"),
+        };
+
+        ListItem::from(name.fg(Color::default()) + format!("  {age}, {count}").fg(Color::DarkGray))
+    }))
+    .highlight_symbol("> ")
+    .highlight_style(Style::default().fg(Color::Green));
+
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+    frame.render_stateful_widget(
+        list.block(
+            Block::bordered()
+                .fg(Color::Cyan)
+                .padding(Padding::horizontal(1))
+                .title(
+                    " Restore From Backup "
+                        .to_span()
+                        .fg(Color::Yellow)
+                        .into_centered_line(),
+                )
+                .title_bottom(
+                    (" Up ".to_span().fg(Color::Yellow)
+                        + "[k]".to_span().fg(Color::Green)
+                        + " Down ".to_span().fg(Color::Yellow)
+                        + "[j]".to_span().fg(Color::Green)
+                        + " Restore ".to_span().fg(Color::Yellow)
+                        + "[Enter]".to_span().fg(Color::Green)
+                        + " Cancel ".to_span().fg(Color::Yellow)
+                        + "[Esc] ".to_span().fg(Color::Green))
+                    .alignment(ratatui::layout::HorizontalAlignment::Center),
+                )
+                .border_type(BorderType::Rounded),
+        ),
+        popup_area,
+        &mut app_state.backup_list_state,
+    );
+}
+
+fn format_age(age: std::time::Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
     }
 }
 
-fn render_add(frame: &mut Frame, app_state: &mut AppState) {
+fn render_message(frame: &mut Frame, title: &str, text: Vec<Line>, title_bottom: Line) {
     let area = frame.area();
-    let popup_width = (area.width as f32 * 0.3) as u16;
-    let popup_height = (area.height as f32 * 0.4) as u16;
-    let popup_x = (area.width - popup_width) / 2;
-    let popup_y = (area.height - popup_height) / 2;
+    let popup_width = (area.width / 2).max(20).min(area.width);
+    let inner_width = popup_width.saturating_sub(4).max(1) as usize;
+    // rough line count after wrapping, plus borders and padding
+    let text_height: usize = text
+        .iter()
+        .map(|line| line.width().div_ceil(inner_width).max(1))
+        .sum();
+    let popup_height = (text_height as u16 + 4).min(area.height);
+
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
 
     let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
 
+    frame.render_widget(ratatui::widgets::Clear, popup_area);
+
+    Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::bordered()
+                .fg(Color::Cyan)
+                .padding(Padding::horizontal(1))
+                .title(title.to_span().fg(Color::Yellow).into_centered_line())
+                .title_bottom(title_bottom.alignment(ratatui::layout::HorizontalAlignment::Center))
+                .border_type(BorderType::Rounded),
+        )
+        .render(popup_area, frame.buffer_mut());
+}
+
+fn render_add(frame: &mut Frame, app_state: &mut AppState) {
+    let popup_area = form_area(frame.area());
+
     let popup_block = Block::bordered()
         .title(
-            " Add New Item "
-                .to_span()
-                .fg(Color::Yellow)
-                .into_centered_line(),
+            if app_state.add_parent.is_some() {
+                " Add Subtask "
+            } else {
+                " Add New Item "
+            }
+            .fg(Color::Yellow)
+            .into_centered_line(),
         )
         .title_bottom(
             (" Next ".to_span().fg(Color::Yellow)
                 + "[Tab]".to_span().fg(Color::Green)
+                + " Editor ".to_span().fg(Color::Yellow)
+                + "[C-o]".to_span().fg(Color::Green)
                 + " Submit ".to_span().fg(Color::Yellow)
                 + "[Enter]".to_span().fg(Color::Green)
                 + " Cancel ".to_span().fg(Color::Yellow)
@@ -430,73 +2994,216 @@ fn render_add(frame: &mut Frame, app_state: &mut AppState) {
 
     frame.render_widget(popup_block, popup_area);
 
-    let [title_area, description_area] =
-        Layout::vertical([Constraint::Length(3), Constraint::Min(0)])
-            .margin(1)
-            .areas(popup_area);
-
-    Paragraph::new(
-        " ".to_span()
-            + app_state
-                .input_state
-                .name_input
-                .as_str()
-                .fg(Color::default())
-            + if app_state.input_state.select_state == InputSelectState::Name {
-                "|".to_span().fg(Color::default())
-            } else {
-                "".to_span()
-            },
-    )
-    .scroll((
-        0,
-        app_state
-            .input_state
-            .name_input
-            .len()
-            .saturating_sub(title_area.width as usize - 4) as u16,
-    ))
-    .block(
+    let [title_area, description_area, due_area, tags_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(3),
+        Constraint::Length(3),
+    ])
+    .margin(1)
+    .areas(popup_area);
+
+    let is_selected = app_state.input_state.select_state == InputSelectState::Name;
+    crate::input::render_line(
+        frame,
+        title_area,
+        &app_state.input_state.name_input,
         Block::bordered()
             .title(" Title ".fg(Color::Yellow))
-            .fg(
-                if app_state.input_state.select_state == InputSelectState::Name {
-                    Color::White
-                } else {
-                    Color::Green
-                },
-            )
-            .border_type(BorderType::Rounded),
-    )
-    .render(title_area, frame.buffer_mut());
-
-    Paragraph::new(
-        app_state
-            .input_state
-            .description_input
-            .as_str()
-            .fg(Color::default())
-            + if app_state.input_state.select_state == InputSelectState::Description {
-                "|".to_span().fg(Color::default())
+            .fg(if is_selected {
+                Color::White
             } else {
-                "".to_span()
-            },
-    )
-    .wrap(Wrap { trim: false })
-    .block(
+                Color::Green
+            })
+            .border_type(BorderType::Rounded),
+        is_selected,
+    );
+
+    let is_selected = app_state.input_state.select_state == InputSelectState::Description;
+    crate::input::render_wrapped(
+        frame,
+        description_area,
+        &mut app_state.input_state.description_input,
         Block::bordered()
             .title(" Description ".fg(Color::Yellow))
-            .fg(
-                if app_state.input_state.select_state == InputSelectState::Description {
+            .title_bottom(if is_selected {
+                (" Newline ".to_span().fg(Color::Yellow)
+                    + "[Alt-Enter] ".to_span().fg(Color::Green))
+                .right_aligned()
+            } else {
+                Line::default()
+            })
+            .fg(if is_selected {
+                Color::White
+            } else {
+                Color::Green
+            })
+            .padding(Padding::uniform(1))
+            .border_type(BorderType::Rounded),
+        is_selected,
+    );
+
+    let [due_area, priority_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(12)]).areas(due_area);
+
+    render_due_input(frame, due_area, app_state);
+    render_priority_input(frame, priority_area, app_state);
+    let [tags_area, recurrence_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(tags_area);
+
+    render_tags_input(frame, tags_area, app_state);
+    render_recurrence_input(frame, recurrence_area, app_state);
+}
+
+// the add and edit forms take a share of the screen, but never less than their rows need
+fn form_area(area: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    let popup_width = ((area.width as f32 * 0.3) as u16)
+        .max(FORM_MIN_WIDTH)
+        .min(area.width);
+    let popup_height = ((area.height as f32 * 0.4) as u16)
+        .max(FORM_MIN_HEIGHT)
+        .min(area.height);
+    let popup_x = (area.width - popup_width) / 2;
+    let popup_y = (area.height - popup_height) / 2;
+
+    ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height)
+}
+
+fn render_due_input(frame: &mut Frame, area: ratatui::layout::Rect, app_state: &AppState) {
+    let is_selected = app_state.input_state.select_state == InputSelectState::Due;
+    let parsed = crate::due::parse(app_state.input_state.due_input.text());
+
+    let title = match &parsed {
+        Err(e) => format!(" {e} ").fg(Color::Red),
+        Ok(_) => " Due ".fg(Color::Yellow),
+    };
+
+    crate::input::render_line(
+        frame,
+        area,
+        &app_state.input_state.due_input,
+        Block::bordered()
+            .title(title)
+            .fg(if parsed.is_err() {
+                Color::Red
+            } else if is_selected {
+                Color::White
+            } else {
+                Color::Green
+            })
+            .border_type(BorderType::Rounded),
+        is_selected,
+    );
+}
+
+fn render_priority_input(frame: &mut Frame, area: ratatui::layout::Rect, app_state: &AppState) {
+    let is_selected = app_state.input_state.select_state == InputSelectState::Priority;
+    let priority = app_state.input_state.priority;
+
+    Paragraph::new(priority.label().fg(priority.color()))
+        .alignment(ratatui::layout::HorizontalAlignment::Center)
+        .block(
+            Block::bordered()
+                .title(" Priority ".fg(Color::Yellow))
+                .title_bottom(if is_selected {
+                    " ◀ ▶ ".to_span().into_centered_line()
+                } else {
+                    Line::default()
+                })
+                .fg(if is_selected {
                     Color::White
                 } else {
                     Color::Green
-                },
-            )
-            .padding(Padding::uniform(1))
+                })
+                .border_type(BorderType::Rounded),
+        )
+        .render(area, frame.buffer_mut());
+}
+
+fn render_recurrence_input(frame: &mut Frame, area: ratatui::layout::Rect, app_state: &AppState) {
+    let is_selected = app_state.input_state.select_state == InputSelectState::Recurrence;
+    let parsed = Recurrence::parse(app_state.input_state.recurrence_input.text());
+
+    let title = match &parsed {
+        Err(e) => format!(" {e} ").fg(Color::Red),
+        Ok(_) => " Repeat ".fg(Color::Yellow),
+    };
+
+    crate::input::render_line(
+        frame,
+        area,
+        &app_state.input_state.recurrence_input,
+        Block::bordered()
+            .title(title)
+            .fg(if parsed.is_err() {
+                Color::Red
+            } else if is_selected {
+                Color::White
+            } else {
+                Color::Green
+            })
             .border_type(BorderType::Rounded),
-    )
-    .render(description_area, frame.buffer_mut());
+        is_selected,
+    );
+}
+
+fn render_tags_input(frame: &mut Frame, area: ratatui::layout::Rect, app_state: &AppState) {
+    let is_selected = app_state.input_state.select_state == InputSelectState::Tags;
+
+    crate::input::render_line(
+        frame,
+        area,
+        &app_state.input_state.tags_input,
+        Block::bordered()
+            .title(" Tags ".fg(Color::Yellow))
+            .fg(if is_selected {
+                Color::White
+            } else {
+                Color::Green
+            })
+            .border_type(BorderType::Rounded),
+        is_selected,
+    );
+}
+
+fn tag_chips(tags: &[String]) -> Vec<ratatui::text::Span<'static>> {
+    tags.iter()
+        .flat_map(|tag| {
+            [
+                " ".to_span(),
+                format!(" #{tag} ")
+                    .fg(Color::Black)
+                    .bg(crate::tags::color(tag)),
+            ]
+        })
+        .collect()
+}
+
+// splits text into spans with the search matches highlighted
+fn highlight_matches<'a>(text: &'a str, query: &str) -> Vec<ratatui::text::Span<'a>> {
+    let mut spans = Vec::new();
+    let mut last = 0;
+
+    for range in crate::search::find(text, query) {
+        spans.push(ratatui::text::Span::raw(&text[last..range.start]));
+        spans.push(text[range.clone()].fg(Color::Black).bg(Color::Yellow));
+        last = range.end;
+    }
+    spans.push(ratatui::text::Span::raw(&text[last..]));
+
+    spans
+}
+
+fn due_color(item: &TodoItem) -> Color {
+    match item
+        .due_date
+        .map(|date| crate::due::state(date, item.due_time))
+    {
+        _ if item.is_done => Color::DarkGray,
+        Some(crate::due::DueState::Overdue) => Color::Red,
+        Some(crate::due::DueState::Today) => Color::Yellow,
+        _ => Color::default(),
+    }
 }
 
 fn render_list(frame: &mut Frame, app_state: &mut AppState) {
@@ -504,14 +3211,79 @@ fn render_list(frame: &mut Frame, app_state: &mut AppState) {
         .margin(3)
         .areas(frame.area());
 
-    let list = List::new(app_state.items.iter().map(|x| {
-        let value = if x.is_done {
-            x.name.to_span().crossed_out()
-        } else {
-            x.name.to_span()
-        };
+    let highlight_width = if app_state.list_state.selected().is_some() {
+        2
+    } else {
+        0
+    };
+    let line_width = (inner_area.width as usize).saturating_sub(highlight_width);
+
+    // only reserve a column for priority markers when something actually has a priority
+    let show_priority = app_state.items.iter().any(|x| !x.priority.is_none());
+    // and for fold markers when something has subtasks
+    let show_folds = app_state.items.iter().any(|x| x.depth > 0);
+
+    let list = List::new(visible_items(app_state).into_iter().map(|idx| {
+        let x = &app_state.items[idx];
+
+        let mut line = Line::from("  ".repeat(x.depth));
+        if show_folds {
+            line.push_span(if !crate::tree::has_children(&app_state.items, idx) {
+                "  ".to_span()
+            } else if x.collapsed {
+                "▸ ".to_span().fg(Color::Cyan)
+            } else {
+                "▾ ".to_span().fg(Color::Cyan)
+            });
+        }
+        if show_priority {
+            line.push_span(if x.priority.is_none() {
+                "  ".to_span()
+            } else {
+                "● ".to_span().fg(x.priority.color())
+            });
+        }
+        let mut suffix = Vec::new();
+        if x.recurrence.is_some() {
+            suffix.push(" ↻".fg(Color::Cyan));
+        }
+
+        let (done, total) = crate::tree::progress(&app_state.items, idx);
+        if total > 0 {
+            suffix.push(format!(" {done}/{total}").fg(if done == total {
+                Color::Green
+            } else {
+                Color::DarkGray
+            }));
+        }
+
+        suffix.extend(tag_chips(&x.tags));
+
+        let due = x.due_date.map(|date| crate::due::format(date, x.due_time));
+        let suffix_width = suffix.iter().map(|span| span.width()).sum::<usize>()
+            + due.as_ref().map_or(0, |due| due.len() + 1);
+
+        // the name gives way to everything else on the line, down to a few columns
+        let name_width = line_width
+            .saturating_sub(line.width() + suffix_width)
+            .max(MIN_NAME_WIDTH);
+        let (name, is_cut) = crate::text::truncate(&x.name, name_width);
+        let value = highlight_matches(name, &app_state.search_query)
+            .into_iter()
+            .chain(is_cut.then(|| crate::text::ELLIPSIS.to_span()))
+            .map(|span| if x.is_done { span.crossed_out() } else { span });
+        line.extend(value);
+        line.extend(suffix);
+
+        if let Some(due) = due {
+            // right align the due date
+            let gap = line_width.saturating_sub(line.width() + due.len()).max(1);
+
+            line.push_span(" ".repeat(gap));
+            line.push_span(due.fg(due_color(x)));
+        }
 
-        ListItem::from(value).fg(Color::default())
+        ListItem::from(line).fg(Color::default())
     }))
     .highlight_symbol("> ")
     .highlight_style(Style::default().fg(Color::Green));
@@ -537,15 +3309,29 @@ fn render_list(frame: &mut Frame, app_state: &mut AppState) {
 
 fn render_delete(frame: &mut Frame, app_state: &mut AppState) {
     let area = frame.area();
-    let selected_item_name = app_state
-        .list_state
-        .selected()
-        .and_then(|idx| app_state.items.get(idx))
-        .map(|item| item.name.clone())
+    let selected_item_name = selected_index(app_state)
+        .map(|idx| app_state.items[idx].name.clone())
         .unwrap_or(String::from("Unnamed Item"));
+    let subtask_count = selected_index(app_state)
+        .map(|idx| crate::tree::subtree_end(&app_state.items, idx) - idx - 1)
+        .unwrap_or(0);
+
+    let subtasks = match subtask_count {
+        0 => String::new(),
+        1 => String::from(" and 1 subtask"),
+        n => format!(" and {n} subtasks"),
+    };
 
-    let text_line =
-        "Delete Item: ".to_span().fg(Color::Yellow) + selected_item_name.to_span().fg(Color::Green);
+    let label = "Delete Item: ";
+    // cut long names so the popup, with its borders and padding, stays on screen
+    let name_width = (area.width as usize).saturating_sub(label.len() + subtasks.len() + 6);
+    let (name, is_cut) = crate::text::truncate(&selected_item_name, name_width);
+
+    let mut text_line = label.to_span().fg(Color::Yellow) + name.to_span().fg(Color::Green);
+    if is_cut {
+        text_line.push_span(crate::text::ELLIPSIS.fg(Color::Green));
+    }
+    text_line.push_span(subtasks.fg(Color::Yellow));
 
     let text_width = text_line.width() as u16;
 
@@ -576,11 +3362,10 @@ fn render_delete(frame: &mut Frame, app_state: &mut AppState) {
 }
 
 fn render_item(frame: &mut Frame, app_state: &mut AppState) {
-    let item = app_state
-        .list_state
-        .selected()
-        .and_then(|idx| app_state.items.get_mut(idx))
-        .unwrap();
+    let Some(index) = selected_index(app_state) else {
+        return;
+    };
+    let item = &app_state.items[index];
 
     let area = frame.area();
     let popup_width = (area.width as f32 * 0.3) as u16;
@@ -594,6 +3379,11 @@ fn render_item(frame: &mut Frame, app_state: &mut AppState) {
 
     let popup_block = Block::bordered()
         .title(title.to_span().fg(Color::Yellow).into_centered_line())
+        .title(
+            format!(" #{} ", item.id)
+                .fg(Color::DarkGray)
+                .into_right_aligned_line(),
+        )
         .title_bottom(
             (" Close ".to_span().fg(Color::Yellow) + "[Esc]".to_span().fg(Color::Green))
                 .alignment(ratatui::layout::HorizontalAlignment::Center),
@@ -604,40 +3394,126 @@ fn render_item(frame: &mut Frame, app_state: &mut AppState) {
 
     frame.render_widget(popup_block, popup_area);
 
-    let [title_area, description_area] =
-        Layout::vertical([Constraint::Length(3), Constraint::Min(0)])
-            .margin(1)
-            .areas(popup_area);
+    let timestamps: Vec<String> = [
+        ("created", item.created_at),
+        ("modified", item.modified_at),
+        ("completed", item.completed_at),
+    ]
+    .into_iter()
+    .filter_map(|(label, at)| at.map(|at| format!("{label} {}", at.format("%Y-%m-%d %H:%M"))))
+    .collect();
+
+    let [
+        title_area,
+        description_area,
+        due_area,
+        recurrence_area,
+        timestamps_area,
+    ] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(if item.due_date.is_some() { 3 } else { 0 }),
+        Constraint::Length(if item.recurrence.is_some() { 3 } else { 0 }),
+        Constraint::Length(timestamps.len() as u16),
+    ])
+    .margin(1)
+    .areas(popup_area);
 
-    Paragraph::new(" ".to_span() + item.name.as_str().fg(Color::default()))
+    Paragraph::new(
+        timestamps
+            .into_iter()
+            .map(|line| Line::from(line.fg(Color::DarkGray)))
+            .collect::<Vec<_>>(),
+    )
+    .alignment(ratatui::layout::HorizontalAlignment::Center)
+    .render(timestamps_area, frame.buffer_mut());
+
+    if let Some(recurrence) = &item.recurrence {
+        let history = match item.completions.last() {
+            None => String::from("never completed"),
+            Some(last) => format!(
+                "done {}x, last {}",
+                item.completions.len(),
+                last.format("%Y-%m-%d %H:%M")
+            ),
+        };
+
+        Paragraph::new(
+            " ".to_span()
+                + recurrence.to_string().fg(Color::default())
+                + format!("  {history}").fg(Color::DarkGray),
+        )
         .block(
             Block::bordered()
-                .title(" Title ".fg(Color::Yellow))
+                .title(" Repeat ".fg(Color::Yellow))
                 .fg(Color::Green)
                 .border_type(BorderType::Rounded),
         )
-        .render(title_area, frame.buffer_mut());
+        .render(recurrence_area, frame.buffer_mut());
+    }
 
-    Paragraph::new(item.description.as_str().fg(Color::default()))
-        .wrap(Wrap { trim: false })
+    if let Some(date) = item.due_date {
+        Paragraph::new(" ".to_span() + crate::due::format(date, item.due_time).fg(due_color(item)))
+            .block(
+                Block::bordered()
+                    .title(" Due ".fg(Color::Yellow))
+                    .fg(Color::Green)
+                    .border_type(BorderType::Rounded),
+            )
+            .render(due_area, frame.buffer_mut());
+    }
+
+    Paragraph::new(" ".to_span() + item.name.as_str().fg(Color::default()))
         .block(
             Block::bordered()
-                .title(" Description ".fg(Color::Yellow))
+                .title(" Title ".fg(Color::Yellow))
+                .title_bottom(Line::from(tag_chips(&item.tags)))
+                .title(if item.priority.is_none() {
+                    Line::default()
+                } else {
+                    format!(" {} ", item.priority.label())
+                        .fg(item.priority.color())
+                        .into_right_aligned_line()
+                })
                 .fg(Color::Green)
-                .padding(Padding::uniform(1))
                 .border_type(BorderType::Rounded),
         )
-        .render(description_area, frame.buffer_mut());
+        .render(title_area, frame.buffer_mut());
+
+    let description_block = Block::bordered()
+        .title(" Description ".fg(Color::Yellow))
+        .fg(Color::Green)
+        .padding(Padding::uniform(1))
+        .border_type(BorderType::Rounded);
+    let inner = description_block.inner(description_area);
+    let rows = crate::input::rows(&item.description, inner.width.max(1) as usize);
+    let max_scroll = rows.len().saturating_sub(inner.height as usize);
+    app_state.item_scroll = app_state.item_scroll.min(max_scroll);
+
+    Paragraph::new(
+        rows.iter()
+            .map(|range| Line::from(&item.description[range.clone()]).fg(Color::default()))
+            .collect::<Vec<_>>(),
+    )
+    .scroll((app_state.item_scroll as u16, 0))
+    .block(if max_scroll > 0 {
+        description_block.title_bottom(
+            format!(
+                " {}/{} [j/k] ",
+                app_state.item_scroll + inner.height as usize,
+                rows.len()
+            )
+            .fg(Color::Yellow)
+            .into_right_aligned_line(),
+        )
+    } else {
+        description_block
+    })
+    .render(description_area, frame.buffer_mut());
 }
 
 fn render_edit(frame: &mut Frame, app_state: &mut AppState) {
-    let area = frame.area();
-    let popup_width = (area.width as f32 * 0.3) as u16;
-    let popup_height = (area.height as f32 * 0.4) as u16;
-    let popup_x = (area.width - popup_width) / 2;
-    let popup_y = (area.height - popup_height) / 2;
-
-    let popup_area = ratatui::layout::Rect::new(popup_x, popup_y, popup_width, popup_height);
+    let popup_area = form_area(frame.area());
 
     let popup_block = Block::bordered()
         .title(
@@ -649,6 +3525,8 @@ fn render_edit(frame: &mut Frame, app_state: &mut AppState) {
         .title_bottom(
             (" Next ".to_span().fg(Color::Yellow)
                 + "[Tab]".to_span().fg(Color::Green)
+                + " Editor ".to_span().fg(Color::Yellow)
+                + "[C-o]".to_span().fg(Color::Green)
                 + " Submit ".to_span().fg(Color::Yellow)
                 + "[Enter]".to_span().fg(Color::Green)
                 + " Cancel ".to_span().fg(Color::Yellow)
@@ -660,71 +3538,63 @@ fn render_edit(frame: &mut Frame, app_state: &mut AppState) {
 
     frame.render_widget(popup_block, popup_area);
 
-    let [title_area, description_area] =
-        Layout::vertical([Constraint::Length(3), Constraint::Min(0)])
-            .margin(1)
-            .areas(popup_area);
-
-    Paragraph::new(
-        " ".to_span()
-            + app_state
-                .input_state
-                .name_input
-                .as_str()
-                .fg(Color::default())
-            + if app_state.input_state.select_state == InputSelectState::Name {
-                "|".to_span().fg(Color::default())
-            } else {
-                "".to_span()
-            },
-    )
-    .scroll((
-        0,
-        app_state
-            .input_state
-            .name_input
-            .len()
-            .saturating_sub(title_area.width as usize - 4) as u16,
-    ))
-    .block(
+    let [title_area, description_area, due_area, tags_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(3),
+        Constraint::Length(3),
+    ])
+    .margin(1)
+    .areas(popup_area);
+
+    let is_selected = app_state.input_state.select_state == InputSelectState::Name;
+    crate::input::render_line(
+        frame,
+        title_area,
+        &app_state.input_state.name_input,
         Block::bordered()
             .title(" Title ".fg(Color::Yellow))
-            .fg(
-                if app_state.input_state.select_state == InputSelectState::Name {
-                    Color::White
-                } else {
-                    Color::Green
-                },
-            )
-            .border_type(BorderType::Rounded),
-    )
-    .render(title_area, frame.buffer_mut());
-
-    Paragraph::new(
-        app_state
-            .input_state
-            .description_input
-            .as_str()
-            .fg(Color::default())
-            + if app_state.input_state.select_state == InputSelectState::Description {
-                "|".to_span().fg(Color::default())
+            .fg(if is_selected {
+                Color::White
             } else {
-                "".to_span()
-            },
-    )
-    .wrap(Wrap { trim: false })
-    .block(
+                Color::Green
+            })
+            .border_type(BorderType::Rounded),
+        is_selected,
+    );
+
+    let is_selected = app_state.input_state.select_state == InputSelectState::Description;
+    crate::input::render_wrapped(
+        frame,
+        description_area,
+        &mut app_state.input_state.description_input,
         Block::bordered()
             .title(" Description ".fg(Color::Yellow))
-            .fg(
-                if app_state.input_state.select_state == InputSelectState::Description {
-                    Color::White
-                } else {
-                    Color::Green
-                },
-            )
+            .title_bottom(if is_selected {
+                (" Newline ".to_span().fg(Color::Yellow)
+                    + "[Alt-Enter] ".to_span().fg(Color::Green))
+                .right_aligned()
+            } else {
+                Line::default()
+            })
+            .fg(if is_selected {
+                Color::White
+            } else {
+                Color::Green
+            })
             .padding(Padding::uniform(1))
             .border_type(BorderType::Rounded),
-    )
-    .render(description_area, frame.buffer_mut());
+        is_selected,
+    );
+
+    let [due_area, priority_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(12)]).areas(due_area);
+
+    render_due_input(frame, due_area, app_state);
+    render_priority_input(frame, priority_area, app_state);
+    let [tags_area, recurrence_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(tags_area);
+
+    render_tags_input(frame, tags_area, app_state);
+    render_recurrence_input(frame, recurrence_area, app_state);
 }
@@ -0,0 +1,74 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    const ALL: [Priority; 5] = [
+        Priority::None,
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::Urgent,
+    ];
+
+    pub fn is_none(&self) -> bool {
+        *self == Priority::None
+    }
+
+    pub fn raise(self) -> Self {
+        Self::ALL[(self as usize + 1).min(Self::ALL.len() - 1)]
+    }
+
+    pub fn lower(self) -> Self {
+        Self::ALL[(self as usize).saturating_sub(1)]
+    }
+
+    // 0 is none, 4 is urgent
+    pub fn from_digit(digit: char) -> Option<Self> {
+        digit
+            .to_digit(10)
+            .and_then(|d| Self::ALL.get(d as usize))
+            .copied()
+    }
+
+    // a label or a digit, e.g. "high" or "3"
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        let mut chars = input.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(digit), None) if digit.is_ascii_digit() => Self::from_digit(digit),
+            _ => Self::ALL.into_iter().find(|p| p.label() == input),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Priority::None => Color::DarkGray,
+            Priority::Low => Color::Blue,
+            Priority::Medium => Color::Yellow,
+            Priority::High => Color::LightRed,
+            Priority::Urgent => Color::Red,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// stored in the list file in the same text form that is typed into the forms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Recurrence {
+    Daily,
+    Weekly(Vec<Weekday>),
+    // day of the month, clamped to the length of shorter months
+    Monthly(u32),
+    // counted from the day the item was completed rather than its due date
+    AfterCompletion(u32),
+}
+
+impl Recurrence {
+    // accepts "daily", "weekly mon,thu", "monthly 15" and "after 3d"
+    pub fn parse(input: &str) -> Result<Option<Self>, String> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return Ok(None);
+        }
+
+        let (kind, argument) = input
+            .split_once(char::is_whitespace)
+            .map_or((input.as_str(), ""), |(kind, argument)| {
+                (kind, argument.trim())
+            });
+
+        let recurrence = match kind {
+            "daily" if argument.is_empty() => Recurrence::Daily,
+            "weekly" => {
+                let mut weekdays = Vec::new();
+                for day in argument
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|day| !day.is_empty())
+                {
+                    let weekday = day
+                        .parse::<Weekday>()
+                        .map_err(|_| format!("Invalid weekday \"{day}\""))?;
+                    if !weekdays.contains(&weekday) {
+                        weekdays.push(weekday);
+                    }
+                }
+
+                if weekdays.is_empty() {
+                    return Err(String::from("Weekly needs weekdays, e.g. weekly mon,thu"));
+                }
+                weekdays.sort_by_key(Weekday::num_days_from_monday);
+                Recurrence::Weekly(weekdays)
+            }
+            "monthly" => match argument.parse::<u32>() {
+                Ok(day @ 1..=31) => Recurrence::Monthly(day),
+                _ => return Err(String::from("Monthly needs a day from 1 to 31")),
+            },
+            "after" => match argument.trim_end_matches('d').parse::<u32>() {
+                Ok(days @ 1..) => Recurrence::AfterCompletion(days),
+                _ => return Err(String::from("After needs a number of days, e.g. after 3d")),
+            },
+            _ => {
+                return Err(format!(
+                    "Invalid repeat \"{input}\", expected daily, weekly, monthly or after"
+                ));
+            }
+        };
+
+        Ok(Some(recurrence))
+    }
+
+    // the first occurrence strictly after both the current due date and today
+    pub fn next(&self, due: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+        let from = due.map_or(today, |due| due.max(today));
+
+        match self {
+            Recurrence::Daily => from + Days::new(1),
+            Recurrence::Weekly(weekdays) => (1..=7)
+                .map(|offset| from + Days::new(offset))
+                .find(|date| weekdays.contains(&date.weekday()))
+                .unwrap_or(from + Days::new(7)),
+            Recurrence::Monthly(day) => {
+                let this_month = clamp_day(from.year(), from.month(), *day);
+                if this_month > from {
+                    this_month
+                } else if from.month() == 12 {
+                    clamp_day(from.year() + 1, 1, *day)
+                } else {
+                    clamp_day(from.year(), from.month() + 1, *day)
+                }
+            }
+            Recurrence::AfterCompletion(days) => today + Days::new((*days).into()),
+        }
+    }
+}
+
+fn clamp_day(year: i32, month: u32, day: u32) -> NaiveDate {
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .unwrap_or_default()
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) => {
+                let days: Vec<String> = weekdays
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect();
+                write!(f, "weekly {}", days.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly {day}"),
+            Recurrence::AfterCompletion(days) => write!(f, "after {days}d"),
+        }
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Recurrence::parse(&value)?.ok_or(String::from("Empty repeat rule"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn next_daily_and_weekly() {
+        // 2026-01-28 is a Wednesday
+        let today = date(2026, 1, 28);
+        let weekly = Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]);
+
+        assert_eq!(Recurrence::Daily.next(None, today), date(2026, 1, 29));
+        assert_eq!(weekly.next(None, today), date(2026, 1, 29));
+        assert_eq!(
+            weekly.next(Some(date(2026, 1, 29)), today),
+            date(2026, 2, 2)
+        );
+        // an overdue item moves on from today, not from its old due date
+        assert_eq!(
+            Recurrence::Daily.next(Some(date(2026, 1, 1)), today),
+            date(2026, 1, 29)
+        );
+    }
+
+    #[test]
+    fn next_monthly_clamps_to_short_months() {
+        let monthly = Recurrence::Monthly(31);
+
+        assert_eq!(
+            monthly.next(Some(date(2026, 1, 31)), date(2026, 1, 31)),
+            date(2026, 2, 28)
+        );
+        assert_eq!(
+            monthly.next(Some(date(2026, 12, 31)), date(2026, 12, 31)),
+            date(2027, 1, 31)
+        );
+        assert_eq!(
+            Recurrence::Monthly(15).next(None, date(2026, 3, 10)),
+            date(2026, 3, 15)
+        );
+    }
+
+    #[test]
+    fn next_after_completion_counts_from_today() {
+        let today = date(2026, 1, 28);
+
+        assert_eq!(
+            Recurrence::AfterCompletion(3).next(Some(date(2026, 2, 10)), today),
+            date(2026, 1, 31)
+        );
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for input in ["daily", "weekly mon,thu", "monthly 15", "after 3d"] {
+            let recurrence = Recurrence::parse(input).unwrap().unwrap();
+            assert_eq!(recurrence.to_string(), input);
+        }
+    }
+}
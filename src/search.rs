@@ -0,0 +1,66 @@
+use crate::TodoItem;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::ops::Range;
+
+// smart-case: a query with capitals has to match exactly, otherwise case is ignored
+pub fn find(text: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let query: Vec<char> = query.chars().collect();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i + query.len() <= chars.len() {
+        let is_match = chars[i..i + query.len()]
+            .iter()
+            .zip(&query)
+            .all(|(&(_, a), &b)| {
+                if case_sensitive {
+                    a == b
+                } else {
+                    a.to_lowercase().eq(b.to_lowercase())
+                }
+            });
+
+        if is_match {
+            let end = chars
+                .get(i + query.len())
+                .map_or(text.len(), |&(idx, _)| idx);
+            ranges.push(chars[i].0..end);
+            i += query.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    ranges
+}
+
+pub fn matches(item: &TodoItem, query: &str) -> bool {
+    !find(&item.name, query).is_empty() || !find(&item.description, query).is_empty()
+}
+
+// the best fuzzy score over the name, description and tags, None when nothing matches
+pub fn fuzzy_score(matcher: &SkimMatcherV2, item: &TodoItem, query: &str) -> Option<i64> {
+    [
+        item.name.as_str(),
+        item.description.as_str(),
+        crate::tags::format(&item.tags).as_str(),
+    ]
+    .into_iter()
+    .filter_map(|text| matcher.fuzzy_match(text, query))
+    .max()
+}
+
+// char indices of the name that the query matched, for highlighting
+pub fn fuzzy_indices(matcher: &SkimMatcherV2, text: &str, query: &str) -> Vec<usize> {
+    matcher
+        .fuzzy_indices(text, query)
+        .map(|(_, indices)| indices)
+        .unwrap_or_default()
+}
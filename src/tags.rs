@@ -0,0 +1,73 @@
+use crate::TodoItem;
+use ratatui::style::Color;
+use std::collections::BTreeMap;
+
+const CHIP_COLORS: [Color; 6] = [
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::LightRed,
+];
+
+// "#work review, #home" -> ["work", "review", "home"]
+pub fn parse(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for tag in input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|tag| tag.trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    tags
+}
+
+pub fn format(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{tag}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// the same tag always gets the same colour
+pub fn color(tag: &str) -> Color {
+    let hash = tag.to_lowercase().bytes().fold(0usize, |acc, b| {
+        acc.wrapping_mul(31).wrapping_add(b as usize)
+    });
+
+    CHIP_COLORS[hash % CHIP_COLORS.len()]
+}
+
+// every tag in use with the number of items carrying it, sorted by name
+pub fn counts(items: &[TodoItem]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+
+    for tag in items.iter().flat_map(|item| &item.tags) {
+        counts
+            .entry(tag.to_lowercase())
+            .or_insert_with(|| (tag.clone(), 0))
+            .1 += 1;
+    }
+
+    counts.into_values().collect()
+}
+
+pub fn has_tag(item: &TodoItem, tag: &str) -> bool {
+    item.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+pub fn matches(item: &TodoItem, filter: &[String], match_all: bool) -> bool {
+    if filter.is_empty() {
+        true
+    } else if match_all {
+        filter.iter().all(|tag| has_tag(item, tag))
+    } else {
+        filter.iter().any(|tag| has_tag(item, tag))
+    }
+}
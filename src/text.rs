@@ -0,0 +1,24 @@
+// Fitting text into a number of terminal columns, counting by display width rather than bytes.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+pub const ELLIPSIS: &str = "…";
+
+// all of text when it fits in width, otherwise the longest start that fits along with an ellipsis
+pub fn truncate(text: &str, width: usize) -> (&str, bool) {
+    if text.width() <= width {
+        return (text, false);
+    }
+
+    let mut used = 0;
+    let end = text
+        .grapheme_indices(true)
+        .find(|(_, grapheme)| {
+            used += grapheme.width();
+            used + ELLIPSIS.width() > width
+        })
+        .map_or(text.len(), |(idx, _)| idx);
+
+    (&text[..end], true)
+}
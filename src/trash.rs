@@ -0,0 +1,46 @@
+// Deleted items go to their list's trash instead of disappearing, and stay there until they are
+// purged by hand or once they are older than the configured retention period.
+
+use crate::{TodoItem, TodoList};
+use chrono::NaiveDateTime;
+
+pub fn delete(items: &mut Vec<TodoItem>, trash: &mut Vec<TodoItem>, idx: usize) {
+    let mut subtree = crate::tree::take_subtree(items, idx);
+    subtree[0].deleted_at = Some(crate::now());
+    trash.extend(subtree);
+}
+
+// moves the trashed subtree at idx back to the end of the list, returning its new index
+pub fn restore(items: &mut Vec<TodoItem>, trash: &mut Vec<TodoItem>, idx: usize) -> usize {
+    let mut subtree = crate::tree::take_subtree(trash, idx);
+    subtree[0].deleted_at = None;
+
+    let new_idx = items.len();
+    items.extend(subtree);
+    new_idx
+}
+
+pub fn purge(trash: &mut Vec<TodoItem>, idx: usize) {
+    crate::tree::take_subtree(trash, idx);
+}
+
+// returns how many items were purged
+pub fn purge_older_than(lists: &mut [TodoList], cutoff: NaiveDateTime) -> usize {
+    let mut purged = 0;
+
+    for list in lists {
+        let mut idx = 0;
+        while idx < list.trash.len() {
+            if list.trash[idx]
+                .deleted_at
+                .is_some_and(|deleted_at| deleted_at < cutoff)
+            {
+                purged += crate::tree::take_subtree(&mut list.trash, idx).len();
+            } else {
+                idx = crate::tree::subtree_end(&list.trash, idx);
+            }
+        }
+    }
+
+    purged
+}
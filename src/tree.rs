@@ -0,0 +1,123 @@
+// Items are stored flat in display order, with each item's `depth` giving its level in the tree.
+// An item's subtree is the run of items directly after it that are deeper than it.
+
+use crate::TodoItem;
+
+// one past the last item in the subtree rooted at idx
+pub fn subtree_end(items: &[TodoItem], idx: usize) -> usize {
+    let depth = items[idx].depth;
+    items[idx + 1..]
+        .iter()
+        .position(|item| item.depth <= depth)
+        .map_or(items.len(), |offset| idx + 1 + offset)
+}
+
+pub fn has_children(items: &[TodoItem], idx: usize) -> bool {
+    items
+        .get(idx + 1)
+        .is_some_and(|next| next.depth > items[idx].depth)
+}
+
+pub fn children(items: &[TodoItem], idx: usize) -> Vec<usize> {
+    let depth = items[idx].depth;
+    (idx + 1..subtree_end(items, idx))
+        .filter(|&child| items[child].depth == depth + 1)
+        .collect()
+}
+
+pub fn roots(items: &[TodoItem]) -> Vec<usize> {
+    (0..items.len())
+        .filter(|&idx| items[idx].depth == 0)
+        .collect()
+}
+
+pub fn parent(items: &[TodoItem], idx: usize) -> Option<usize> {
+    let depth = items[idx].depth;
+    (0..idx).rev().find(|&i| items[i].depth < depth)
+}
+
+// (done, total) over the direct children
+pub fn progress(items: &[TodoItem], idx: usize) -> (usize, usize) {
+    let children = children(items, idx);
+    let done = children
+        .iter()
+        .filter(|&&child| items[child].is_done)
+        .count();
+    (done, children.len())
+}
+
+pub fn open_descendants(items: &[TodoItem], idx: usize) -> usize {
+    items[idx + 1..subtree_end(items, idx)]
+        .iter()
+        .filter(|item| !item.is_done)
+        .count()
+}
+
+fn previous_sibling(items: &[TodoItem], idx: usize) -> Option<usize> {
+    let depth = items[idx].depth;
+    (0..idx)
+        .rev()
+        .find(|&i| items[i].depth <= depth)
+        .filter(|&i| items[i].depth == depth)
+}
+
+fn next_sibling(items: &[TodoItem], idx: usize) -> Option<usize> {
+    let end = subtree_end(items, idx);
+    items
+        .get(end)
+        .filter(|next| next.depth == items[idx].depth)
+        .map(|_| end)
+}
+
+// removes the item and its subtree, with the item made top level
+pub fn take_subtree(items: &mut Vec<TodoItem>, idx: usize) -> Vec<TodoItem> {
+    let end = subtree_end(items, idx);
+    let depth = items[idx].depth;
+
+    items
+        .drain(idx..end)
+        .map(|item| TodoItem {
+            depth: item.depth - depth,
+            ..item
+        })
+        .collect()
+}
+
+// each move returns the new index of the moved item
+
+pub fn move_down(items: &mut [TodoItem], idx: usize) -> Option<usize> {
+    let next = next_sibling(items, idx)?;
+    let next_end = subtree_end(items, next);
+
+    items[idx..next_end].rotate_left(next - idx);
+    Some(idx + (next_end - next))
+}
+
+pub fn move_up(items: &mut [TodoItem], idx: usize) -> Option<usize> {
+    let previous = previous_sibling(items, idx)?;
+    let end = subtree_end(items, idx);
+
+    items[previous..end].rotate_left(idx - previous);
+    Some(previous)
+}
+
+// makes the item the last child of its previous sibling
+pub fn indent(items: &mut [TodoItem], idx: usize) -> Option<usize> {
+    let previous = previous_sibling(items, idx)?;
+    let end = subtree_end(items, idx);
+
+    items[previous].collapsed = false;
+    items[idx..end].iter_mut().for_each(|item| item.depth += 1);
+    Some(idx)
+}
+
+// moves the item out of its parent, placing it right after the parent's subtree
+pub fn outdent(items: &mut [TodoItem], idx: usize) -> Option<usize> {
+    let parent = parent(items, idx)?;
+    let end = subtree_end(items, idx);
+    let parent_end = subtree_end(items, parent);
+
+    items[idx..end].iter_mut().for_each(|item| item.depth -= 1);
+    items[idx..parent_end].rotate_left(end - idx);
+    Some(parent_end - (end - idx))
+}